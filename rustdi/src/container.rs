@@ -2,23 +2,155 @@ use std::sync::Mutex;
 use std::sync::RwLock;
 use std::sync::Arc;
 use std::marker::PhantomData;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::time::Duration;
 use typemap::{TypeMap, ShareMap, Key};
 
-use super::service::{Service, ServiceReadGuard, ServiceWriteGuard};
-use super::traits::Resolver;
+use super::generic::TypeList;
+use super::handler::{Access, DependencyError, Handler};
+use super::module::Module;
+use super::pool::Pool;
+use super::service::{Service, ServiceReadGuard, ServiceWriteGuard, OwnedReadGuard, OwnedWriteGuard};
+use super::traits::{Injectable, Resolver, SharedResolver};
+use super::weak::{WeakService, WeakSingleton};
 use super::resolve_error::ResolveError;
 
 // TypeMap requires us to use key and value types
 struct KeyType<T>(PhantomData<T>);
 impl<T: 'static> Key for KeyType<T> { type Value = Service<ServiceContainer, T>; }
 
+// Named services are keyed by service type and then looked up by name
+struct NamedKeyType<T>(PhantomData<T>);
+impl<T: 'static> Key for NamedKeyType<T> { type Value = HashMap<String, Service<ServiceContainer, T>>; }
+
+// Factories which take runtime arguments are keyed by both service and argument type
+type ArgsFactoryFn<T, A> = fn(&ServiceContainer, A) -> Result<T, ResolveError>;
+struct ArgsKeyType<T, A>(PhantomData<(T, A)>);
+impl<T: 'static, A: 'static> Key for ArgsKeyType<T, A> { type Value = ArgsFactoryFn<T, A>; }
+struct NamedArgsKeyType<T, A>(PhantomData<(T, A)>);
+impl<T: 'static, A: 'static> Key for NamedArgsKeyType<T, A> { type Value = HashMap<String, ArgsFactoryFn<T, A>>; }
+
+// Checks whether the service with a given TypeId could be resolved with an access, keyed by TypeId
+// as handler dependencies only know the TypeId of the services they need
+type CheckFn = fn(&ServiceContainer, Access) -> Result<(), ResolveError>;
+
+// The environment variable from_env reads the active profile from
+pub const PROFILE_ENV_VAR: &str = "RUSTDI_PROFILE";
+
 // The ServiceContainer itself: just a wrapper around a TypeMap<Send + Sync>
+// (plus a record of which modules have been installed into it, how to check each bound
+// service, a token for each binding which weak handles to it watch, the active profile,
+// which services have been bound for it, and the name that bindings are currently being
+// made under, if any)
 pub struct ServiceContainer {
-    services: ShareMap
+    services: ShareMap,
+    modules: HashSet<TypeId>,
+    checks: HashMap<TypeId, CheckFn>,
+    bindings: HashMap<(TypeId, Option<String>), Arc<()>>,
+    profile: Option<String>,
+    profile_bindings: HashSet<(TypeId, Option<String>)>,
+    in_profile: bool,
+    binding_name: Option<String>,
+}
+impl Default for ServiceContainer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
+
 impl ServiceContainer {
     pub fn new () -> Self {
-        ServiceContainer{services: TypeMap::custom()}
+        ServiceContainer{services: TypeMap::custom(), modules: HashSet::new(), checks: HashMap::new(), bindings: HashMap::new(), profile: None, profile_bindings: HashSet::new(), in_profile: false, binding_name: None}
+    }
+
+    pub fn with_profile (profile: &str) -> Self {
+        ServiceContainer{profile: Some(profile.to_string()), ..Self::new()}
+    }
+
+    // Create a container whose active profile is taken from the RUSTDI_PROFILE
+    // environment variable (or which has no active profile if it is unset)
+    pub fn from_env () -> Self {
+        ServiceContainer{profile: env::var(PROFILE_ENV_VAR).ok(), ..Self::new()}
+    }
+
+    pub fn profile (&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+}
+
+// Profile-specific bindings. Bindings made for the active profile take precedence over
+// default bindings for the same service, whether the defaults are bound before or after.
+impl ServiceContainer {
+    pub fn when_profile<F: FnOnce(&mut Self)> (&mut self, profile: &str, bind: F) {
+        if self.profile() == Some(profile) {
+            let outer_in_profile = self.in_profile;
+            self.in_profile = true;
+            bind(self);
+            self.in_profile = outer_in_profile;
+        }
+    }
+
+    // Record a binding for the active profile, or check whether a default binding
+    // should be skipped because the service has already been bound for the profile
+    fn allow_binding (&mut self, type_id: TypeId) -> bool {
+        let key = (type_id, self.binding_name.clone());
+        if self.in_profile {
+            self.profile_bindings.insert(key);
+            true
+        } else {
+            !self.profile_bindings.contains(&key)
+        }
+    }
+}
+
+// Named bindings, which allow several services of the same type to be bound and then
+// distinguished by name when resolving. Bindings made inside bind are given the name.
+impl ServiceContainer {
+    pub fn bind_named<F: FnOnce(&mut Self)> (&mut self, name: &str, bind: F) {
+        let outer_name = self.binding_name.replace(name.to_string());
+        bind(self);
+        self.binding_name = outer_name;
+    }
+
+    fn insert_service<S: Send + Sync + 'static> (&mut self, value: Service<Self, S>) {
+        if !self.allow_binding(TypeId::of::<S>()) {
+            return;
+        }
+        self.bindings.insert((TypeId::of::<S>(), self.binding_name.clone()), Arc::new(()));
+        match self.binding_name.clone() {
+            Some(name) => { self.services.entry::<NamedKeyType<S>>().or_insert_with(HashMap::new).insert(name, value); },
+            None       => {
+                self.services.insert::<KeyType<S>>(value);
+                self.checks.insert(TypeId::of::<S>(), Self::check_service::<S>);
+            },
+        }
+    }
+
+    fn insert_args_factory<S: 'static, A: 'static> (&mut self, factory: ArgsFactoryFn<S, A>) {
+        if !self.allow_binding(TypeId::of::<ArgsKeyType<S, A>>()) {
+            return;
+        }
+        match self.binding_name.clone() {
+            Some(name) => { self.services.entry::<NamedArgsKeyType<S, A>>().or_insert_with(HashMap::new).insert(name, factory); },
+            None       => { self.services.insert::<ArgsKeyType<S, A>>(factory); },
+        }
+    }
+}
+
+// Installing modules, which bind a group of related services at once
+impl ServiceContainer {
+    pub fn install<M: Module> (&mut self, module: M) {
+        // Record the module before configuring it so that installing it again
+        // (even from one of its own dependencies) is a no-op. Modules are told apart
+        // by type alone, so a second module of the same type isn't configured at all.
+        if self.modules.insert(TypeId::of::<M>()) {
+            module.configure(self);
+        }
+    }
+
+    pub fn has_module<M: Module> (&self) -> bool {
+        self.modules.contains(&TypeId::of::<M>())
     }
 }
 
@@ -26,22 +158,73 @@ impl ServiceContainer {
 impl ServiceContainer {
     pub fn bind_singleton_arc<S: Send + Sync + 'static> (&mut self, service: Arc<S>) {
         let value = Service::SingletonArc(service);
-        self.services.insert::<KeyType<S>>(value);
+        self.insert_service(value);
     }
 
     pub fn bind_singleton_rwlock<S: Send + Sync + 'static> (&mut self, service: Arc<RwLock<S>>) {
         let value = Service::SingletonRwLock(service);
-        self.services.insert::<KeyType<S>>(value);
+        self.insert_service(value);
     }
 
     pub fn bind_singleton_mutex<S: Send + Sync + 'static> (&mut self, service: Arc<Mutex<S>>) {
         let value = Service::SingletonMutex(service);
-        self.services.insert::<KeyType<S>>(value);
+        self.insert_service(value);
+    }
+
+    // Bind a singleton which can be borrowed like bind_singleton_rwlock, but which also
+    // resolves to a clone of its current value when an owned value is requested
+    pub fn bind_singleton_clone<S: Clone + Send + Sync + 'static> (&mut self, service: Arc<RwLock<S>>) {
+        let value = Service::SingletonClone(Box::new(Service::SingletonRwLock(service)), S::clone);
+        self.insert_service(value);
+    }
+
+    // As bind_singleton_clone, but for an immutable singleton bound like bind_singleton_arc
+    pub fn bind_singleton_arc_clone<S: Clone + Send + Sync + 'static> (&mut self, service: Arc<S>) {
+        let value = Service::SingletonClone(Box::new(Service::SingletonArc(service)), S::clone);
+        self.insert_service(value);
     }
 
     pub fn bind_factory<S: Send + Sync + 'static> (&mut self, factory: fn(&Self) -> S) {
         let value = Service::Factory(Arc::new(factory));
-        self.services.insert::<KeyType<S>>(value);
+        self.insert_service(value);
+    }
+
+    // Bind a factory which may fail to resolve the dependencies of the service it creates
+    pub fn bind_try_factory<S: Send + Sync + 'static> (&mut self, factory: fn(&Self) -> Result<S, ResolveError>) {
+        let value = Service::TryFactory(Arc::new(factory));
+        self.insert_service(value);
+    }
+
+    pub fn bind_injectable<S: Injectable + Send + Sync + 'static> (&mut self) {
+        self.bind_try_factory(S::inject_new);
+    }
+
+    // Bind a factory which constructs services from both resolved dependencies and
+    // arguments supplied by the caller of resolve_with. The factory may fail to resolve
+    // the dependencies of the service it creates.
+    pub fn bind_factory_with_args<S: Send + Sync + 'static, A: 'static> (&mut self, factory: fn(&Self, A) -> Result<S, ResolveError>) {
+        self.insert_args_factory(factory);
+    }
+
+    // Bind the generic factory F for each of the type parameters in the tuple L,
+    // e.g. bind_generic::<RepositoryFactory, (User, Order)>()
+    pub fn bind_generic<F, L: TypeList<F>> (&mut self) {
+        L::bind_all(self);
+    }
+
+    // Bind a pool of at most size instances created by factory. Resolving a reference
+    // checks an instance out of the pool, waiting for one to be returned if all are in use.
+    // Panics if size is 0.
+    pub fn bind_pool<S: Send + Sync + 'static> (&mut self, size: usize, factory: fn(&Self) -> S) {
+        let value = Service::Pool(Arc::new(Pool::new(size, None)), Arc::new(factory));
+        self.insert_service(value);
+    }
+
+    // As bind_pool, but resolving fails with ResolveError::PoolTimeout if no instance
+    // is returned to the pool within timeout
+    pub fn bind_pool_with_timeout<S: Send + Sync + 'static> (&mut self, size: usize, timeout: Duration, factory: fn(&Self) -> S) {
+        let value = Service::Pool(Arc::new(Pool::new(size, Some(timeout))), Arc::new(factory));
+        self.insert_service(value);
     }
 }
 
@@ -51,26 +234,209 @@ impl Resolver for ServiceContainer {
 
     fn resolve_owned_value<S: 'static> (&self) -> Result<S, ResolveError> {
         match self.services.get_unchecked::<KeyType<S>>() {
-            Some(service) => service.owned_value(&self),
+            Some(service) => service.owned_value(self),
             None          => Err(ResolveError::NonExist),
         }
     }
 
-    fn resolve_immutable_ref<S: 'static> (&self) -> Result<ServiceReadGuard<S>, ResolveError> {
+    fn resolve_immutable_ref<S: 'static> (&self) -> Result<ServiceReadGuard<'_, S>, ResolveError> {
         match self.services.get_unchecked::<KeyType<S>>() {
-            Some(service) => service.immutable_ref(&self),
+            Some(service) => service.immutable_ref(self),
             _             => Err(ResolveError::NonExist),
         }
     }
 
-    fn resolve_mutable_ref<S: 'static> (&self) -> Result<ServiceWriteGuard<S>, ResolveError> {
+    fn resolve_mutable_ref<S: 'static> (&self) -> Result<ServiceWriteGuard<'_, S>, ResolveError> {
         match self.services.get_unchecked::<KeyType<S>>() {
-            Some(service) => service.mutable_ref(&self),
+            Some(service) => service.mutable_ref(self),
             _             => Err(ResolveError::NonExist),
         }
     }
+
+    fn is_not_bound (error: &ResolveError) -> bool {
+        matches!(error, ResolveError::NonExist)
+    }
+
+    fn resolve_optional_owned_value<S: 'static> (&self) -> Result<Option<S>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.owned_value(self).map(Some),
+            None          => Ok(None),
+        }
+    }
+
+    fn resolve_optional_immutable_ref<S: 'static> (&self) -> Result<Option<ServiceReadGuard<'_, S>>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.immutable_ref(self).map(Some),
+            None          => Ok(None),
+        }
+    }
+
+    fn resolve_optional_mutable_ref<S: 'static> (&self) -> Result<Option<ServiceWriteGuard<'_, S>>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.mutable_ref(self).map(Some),
+            None          => Ok(None),
+        }
+    }
 }
 
+// Resolving methods for handles to services which don't borrow from the Service Container
+impl SharedResolver for ServiceContainer {
+    fn resolve_arc<S: 'static> (&self) -> Result<Arc<S>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.arc(),
+            None          => Err(ResolveError::NonExist),
+        }
+    }
+
+    fn resolve_rwlock_arc<S: 'static> (&self) -> Result<Arc<RwLock<S>>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.rwlock_arc(),
+            None          => Err(ResolveError::NonExist),
+        }
+    }
+
+    fn resolve_mutex_arc<S: 'static> (&self) -> Result<Arc<Mutex<S>>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.mutex_arc(),
+            None          => Err(ResolveError::NonExist),
+        }
+    }
+
+    fn resolve_owned_read_guard<S: 'static> (&self) -> Result<OwnedReadGuard<S>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.owned_read_guard(self),
+            None          => Err(ResolveError::NonExist),
+        }
+    }
+
+    fn resolve_owned_write_guard<S: 'static> (&self) -> Result<OwnedWriteGuard<S>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.owned_write_guard(self),
+            None          => Err(ResolveError::NonExist),
+        }
+    }
+
+    fn resolve_optional_owned_read_guard<S: 'static> (&self) -> Result<Option<OwnedReadGuard<S>>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.owned_read_guard(self).map(Some),
+            None          => Ok(None),
+        }
+    }
+
+    fn resolve_optional_owned_write_guard<S: 'static> (&self) -> Result<Option<OwnedWriteGuard<S>>, ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.owned_write_guard(self).map(Some),
+            None          => Ok(None),
+        }
+    }
+}
+
+
+// Resolving methods for weak handles to singleton services, which can be upgraded
+// to a read guard for as long as the service stays bound
+impl ServiceContainer {
+    pub fn resolve_weak<S: 'static> (&self) -> Result<WeakService<S>, ResolveError> {
+        let service = match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.weak()?,
+            None          => return Err(ResolveError::NonExist),
+        };
+        self.weak_service(service, None)
+    }
+
+    pub fn resolve_named_weak<S: 'static> (&self, name: &str) -> Result<WeakService<S>, ResolveError> {
+        let service = self.named_service::<S>(name)?.weak()?;
+        self.weak_service(service, Some(name))
+    }
+
+    fn weak_service<S: 'static> (&self, service: WeakSingleton<S>, name: Option<&str>) -> Result<WeakService<S>, ResolveError> {
+        let binding = self.bindings.get(&(TypeId::of::<S>(), name.map(String::from))).ok_or(ResolveError::NonExist)?;
+        Ok(WeakService::new(Arc::downgrade(binding), service))
+    }
+}
+
+// Resolving methods for named services
+impl ServiceContainer {
+    fn named_service<S: 'static> (&self, name: &str) -> Result<&Service<Self, S>, ResolveError> {
+        self.services.get_unchecked::<NamedKeyType<S>>()
+            .and_then(|services| services.get(name))
+            .ok_or(ResolveError::NonExist)
+    }
+
+    pub fn resolve_named_owned_value<S: 'static> (&self, name: &str) -> Result<S, ResolveError> {
+        self.named_service::<S>(name)?.owned_value(self)
+    }
+
+    pub fn resolve_named_immutable_ref<S: 'static> (&self, name: &str) -> Result<ServiceReadGuard<'_, S>, ResolveError> {
+        self.named_service::<S>(name)?.immutable_ref(self)
+    }
+
+    pub fn resolve_named_mutable_ref<S: 'static> (&self, name: &str) -> Result<ServiceWriteGuard<'_, S>, ResolveError> {
+        self.named_service::<S>(name)?.mutable_ref(self)
+    }
+
+    pub fn resolve_named_arc<S: 'static> (&self, name: &str) -> Result<Arc<S>, ResolveError> {
+        self.named_service::<S>(name)?.arc()
+    }
+
+    pub fn resolve_named_rwlock_arc<S: 'static> (&self, name: &str) -> Result<Arc<RwLock<S>>, ResolveError> {
+        self.named_service::<S>(name)?.rwlock_arc()
+    }
+
+    pub fn resolve_named_mutex_arc<S: 'static> (&self, name: &str) -> Result<Arc<Mutex<S>>, ResolveError> {
+        self.named_service::<S>(name)?.mutex_arc()
+    }
+}
+
+// Checking that handlers' dependencies are bound with compatible binding kinds, so that
+// problems can be reported at startup rather than when a handler is first called
+impl ServiceContainer {
+    fn check_service<S: 'static> (&self, access: Access) -> Result<(), ResolveError> {
+        match self.services.get_unchecked::<KeyType<S>>() {
+            Some(service) => service.check_access(access),
+            None          => Err(ResolveError::NonExist),
+        }
+    }
+
+    pub fn check_handler<H: Handler + ?Sized> (&self, handler: &H) -> Result<(), Vec<DependencyError>> {
+        let errors = handler.dependencies().into_iter()
+            .filter_map(|dependency| {
+                let result = match self.checks.get(&dependency.type_id) {
+                    Some(check) => check(self, dependency.access),
+                    None if dependency.optional => Ok(()),
+                    None => Err(ResolveError::NonExist),
+                };
+                result.err().map(|error| DependencyError{handler: handler.name(), dependency, error})
+            })
+            .collect::<Vec<_>>();
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    // Check several handlers at once, reporting the errors for all of them
+    pub fn check_all (&self, handlers: &[&dyn Handler]) -> Result<(), Vec<DependencyError>> {
+        let errors = handlers.iter()
+            .filter_map(|handler| self.check_handler(*handler).err())
+            .flatten()
+            .collect::<Vec<_>>();
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+// Resolving methods for services whose factories take runtime arguments
+impl ServiceContainer {
+    pub fn resolve_with<S: 'static, A: 'static> (&self, args: A) -> Result<S, ResolveError> {
+        match self.services.get_unchecked::<ArgsKeyType<S, A>>() {
+            Some(factory) => factory(self, args),
+            None          => Err(ResolveError::NonExist),
+        }
+    }
+
+    pub fn resolve_named_with<S: 'static, A: 'static> (&self, name: &str, args: A) -> Result<S, ResolveError> {
+        match self.services.get_unchecked::<NamedArgsKeyType<S, A>>().and_then(|factories| factories.get(name)) {
+            Some(factory) => factory(self, args),
+            None          => Err(ResolveError::NonExist),
+        }
+    }
+}
 
 
 // Find a value in the map and get a reference to it.
@@ -0,0 +1,40 @@
+use super::container::ServiceContainer;
+
+// Open-generic bindings: a single factory which is generic over a type parameter, such as
+// one which creates a Repository<T> for any model T.
+//
+// Rust can't instantiate a generic function for a type that is only known at runtime, so
+// the factory is bound once for a whole list of type parameters (a tuple implementing
+// TypeList), after which each instantiation resolves just like any other service.
+pub trait GenericFactory<T> {
+    type Service: Send + Sync + 'static;
+
+    fn create(container: &ServiceContainer) -> Self::Service;
+}
+
+pub trait TypeList<F> {
+    fn bind_all(container: &mut ServiceContainer);
+}
+
+macro_rules! impl_type_list {
+    ($($T:ident),+) => {
+        impl<F, $($T),+> TypeList<F> for ($($T,)+) where $(F: GenericFactory<$T>),+ {
+            fn bind_all(container: &mut ServiceContainer) {
+                $( container.bind_factory(<F as GenericFactory<$T>>::create); )+
+            }
+        }
+    }
+}
+
+impl_type_list!(A);
+impl_type_list!(A, B);
+impl_type_list!(A, B, C);
+impl_type_list!(A, B, C, D);
+impl_type_list!(A, B, C, D, E);
+impl_type_list!(A, B, C, D, E, G);
+impl_type_list!(A, B, C, D, E, G, H);
+impl_type_list!(A, B, C, D, E, G, H, I);
+impl_type_list!(A, B, C, D, E, G, H, I, J);
+impl_type_list!(A, B, C, D, E, G, H, I, J, K);
+impl_type_list!(A, B, C, D, E, G, H, I, J, K, L);
+impl_type_list!(A, B, C, D, E, G, H, I, J, K, L, M);
@@ -0,0 +1,56 @@
+use std::any::{self, TypeId};
+use std::error::Error;
+use std::fmt;
+
+use super::resolve_error::ResolveError;
+
+// The ways in which a handler can ask for one of its dependencies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    ImmutableRef,
+    MutableRef,
+    OwnedValue,
+    SharedArc,
+    SharedRwLock,
+    SharedMutex,
+}
+
+// A service which a handler depends on, and how it accesses it.
+// Optional dependencies are allowed to be unbound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+    pub access: Access,
+    pub optional: bool,
+}
+
+impl Dependency {
+    pub fn new<S: 'static> (access: Access, optional: bool) -> Self {
+        Dependency{type_id: TypeId::of::<S>(), type_name: any::type_name::<S>(), access, optional}
+    }
+}
+
+// Handlers which can list their dependencies up front, so that a container can check that
+// they are all bound (with compatible binding kinds) before any of the handlers are called.
+// #[inject] generates an implementation for each non-generic function, as the constant {name}::Handler.
+pub trait Handler {
+    fn name(&self) -> &'static str;
+    fn dependencies(&self) -> Vec<Dependency>;
+}
+
+// A handler dependency which the container can't provide, and the error resolving it would give
+#[derive(Debug)]
+pub struct DependencyError {
+    pub handler: &'static str,
+    pub dependency: Dependency,
+    pub error: ResolveError,
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handler {} can't resolve {} ({:?}): {}", self.handler, self.dependency.type_name, self.dependency.access, self.error)
+    }
+}
+
+impl Error for DependencyError {}
@@ -2,13 +2,34 @@ extern crate unsafe_any;
 extern crate typemap;
 
 mod traits;
-pub use traits::{Inject, Resolver};
+pub use traits::{FromResolver, HandlerResult, Inject, Injectable, Resolver, SharedResolver};
 
 mod container;
-pub use container::ServiceContainer;
+pub use container::{ServiceContainer, PROFILE_ENV_VAR};
+
+mod module;
+pub use module::Module;
 
 mod service;
-pub use service::{Service, ServiceReadGuard, ServiceWriteGuard};
+pub use service::{Service, ServiceReadGuard, ServiceWriteGuard, OwnedReadGuard, OwnedWriteGuard};
+
+mod owned_guard;
+pub use owned_guard::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, OwnedMutexGuard};
+
+mod mapped_guard;
+pub use mapped_guard::{MappedReadGuard, MappedWriteGuard};
+
+mod weak;
+pub use weak::{WeakService, WeakSingleton};
+
+mod handler;
+pub use handler::{Access, Dependency, DependencyError, Handler};
+
+mod generic;
+pub use generic::{GenericFactory, TypeList};
+
+mod pool;
+pub use pool::{Pool, PoolGuard};
 
 mod resolve_error;
 pub use resolve_error::ResolveError;
\ No newline at end of file
@@ -0,0 +1,87 @@
+use std::ops::Deref;
+use std::ops::DerefMut;
+use std::marker::PhantomData;
+
+// Guards for part of a service, made by mapping a guard for the whole service with e.g.
+// ServiceReadGuard::map(guard, |state| &state.config). The original guard is kept (so any lock
+// stays held) on the heap so that the part stays put even if the service is owned by the guard.
+//
+// map is an associated function rather than a method so that it doesn't hide any map method
+// of the service itself (e.g. if the service is an Option).
+pub struct MappedReadGuard<G, U: ?Sized> {
+    value: *const U,
+    guard: HeldGuard<G>,
+}
+impl<G: Deref, U: ?Sized> MappedReadGuard<G, U> {
+    pub fn new<F: FnOnce(&G::Target) -> &U> (guard: G, project: F) -> Self {
+        let guard = HeldGuard::new(guard);
+        let value = project(unsafe { &**guard.0 }) as *const U;
+        MappedReadGuard{value, guard}
+    }
+}
+impl<G, U: ?Sized> MappedReadGuard<G, U> {
+    pub fn map<V: ?Sized, F: FnOnce(&U) -> &V> (mapped: Self, project: F) -> MappedReadGuard<G, V> {
+        let value = project(unsafe { &*mapped.value }) as *const V;
+        MappedReadGuard{value, guard: mapped.guard}
+    }
+}
+impl<G, U: ?Sized> Deref for MappedReadGuard<G, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.value }
+    }
+}
+
+// (the guard and a reference to the part can be sent or shared if their types allow it)
+unsafe impl<G: Send, U: ?Sized + Sync> Send for MappedReadGuard<G, U> {}
+unsafe impl<G: Sync, U: ?Sized + Sync> Sync for MappedReadGuard<G, U> {}
+
+pub struct MappedWriteGuard<G, U: ?Sized> {
+    value: *mut U,
+    guard: HeldGuard<G>,
+}
+impl<G: DerefMut, U: ?Sized> MappedWriteGuard<G, U> {
+    pub fn new<F: FnOnce(&mut G::Target) -> &mut U> (guard: G, project: F) -> Self {
+        let guard = HeldGuard::new(guard);
+        let value = project(unsafe { &mut **guard.0 }) as *mut U;
+        MappedWriteGuard{value, guard}
+    }
+}
+impl<G, U: ?Sized> MappedWriteGuard<G, U> {
+    pub fn map<V: ?Sized, F: FnOnce(&mut U) -> &mut V> (mapped: Self, project: F) -> MappedWriteGuard<G, V> {
+        let value = project(unsafe { &mut *mapped.value }) as *mut V;
+        MappedWriteGuard{value, guard: mapped.guard}
+    }
+}
+impl<G, U: ?Sized> Deref for MappedWriteGuard<G, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.value }
+    }
+}
+impl<G, U: ?Sized> DerefMut for MappedWriteGuard<G, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.value }
+    }
+}
+
+unsafe impl<G: Send, U: ?Sized + Send> Send for MappedWriteGuard<G, U> {}
+unsafe impl<G: Sync, U: ?Sized + Sync> Sync for MappedWriteGuard<G, U> {}
+
+// The original guard, held through the raw pointer from Box::into_raw rather than as a Box, as
+// moving a Box (e.g. when returning a mapped guard) asserts unique access to its contents, which
+// would invalidate the pointer to the part. It is turned back into a Box to be dropped, including
+// if a projection panics before the mapped guard is made.
+struct HeldGuard<G>(*mut G, PhantomData<G>);
+impl<G> HeldGuard<G> {
+    fn new (guard: G) -> Self {
+        HeldGuard(Box::into_raw(Box::new(guard)), PhantomData)
+    }
+}
+impl<G> Drop for HeldGuard<G> {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(self.0) });
+    }
+}
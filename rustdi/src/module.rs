@@ -0,0 +1,13 @@
+use super::container::ServiceContainer;
+
+// A Module groups related bindings together so that each crate can ship its own
+// bindings rather than funnelling everything through one setup function.
+//
+// Modules which depend on other modules should install them from within configure.
+// Each module type is only ever configured once per container, so it is fine for
+// several modules to install the same dependency. This means that installing a second
+// module of the same type is ignored even if it holds different configuration (e.g.
+// a DbModule{url} for another database): bind such services by name instead.
+pub trait Module: 'static {
+    fn configure(&self, container: &mut ServiceContainer);
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
+use std::mem;
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use super::resolve_error::ResolveError;
+
+// Lock guards which hold a handle to the lock they were taken from rather than borrowing it,
+// so that they are 'static and can be returned from or captured by 'static code (e.g. boxed
+// futures). Like the std guards they wrap they must be dropped on the thread which took them,
+// so they aren't Send: a shared handle (e.g. from resolve_rwlock_arc) is needed to use a
+// service from another thread.
+//
+// The std guard's lifetime is extended to 'static, which is fine as the lock lives on the heap
+// behind the Arc and so stays put for as long as the guard holds the Arc. The guard is declared
+// before the Arc so that it is dropped (releasing the lock) first.
+pub struct OwnedRwLockReadGuard<T: 'static> {
+    guard: RwLockReadGuard<'static, T>,
+    _lock: Arc<RwLock<T>>,
+}
+impl<T> OwnedRwLockReadGuard<T> {
+    pub fn read (lock: Arc<RwLock<T>>) -> Result<Self, ResolveError> {
+        let guard = lock.read().map_err(|_| ResolveError::Poisoned)?;
+        let guard = unsafe { mem::transmute::<RwLockReadGuard<'_, T>, RwLockReadGuard<'static, T>>(guard) };
+        Ok(OwnedRwLockReadGuard{guard, _lock: lock})
+    }
+}
+impl<T> Deref for OwnedRwLockReadGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+pub struct OwnedRwLockWriteGuard<T: 'static> {
+    guard: RwLockWriteGuard<'static, T>,
+    _lock: Arc<RwLock<T>>,
+}
+impl<T> OwnedRwLockWriteGuard<T> {
+    pub fn write (lock: Arc<RwLock<T>>) -> Result<Self, ResolveError> {
+        let guard = lock.write().map_err(|_| ResolveError::Poisoned)?;
+        let guard = unsafe { mem::transmute::<RwLockWriteGuard<'_, T>, RwLockWriteGuard<'static, T>>(guard) };
+        Ok(OwnedRwLockWriteGuard{guard, _lock: lock})
+    }
+}
+impl<T> Deref for OwnedRwLockWriteGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+impl<T> DerefMut for OwnedRwLockWriteGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+pub struct OwnedMutexGuard<T: 'static> {
+    guard: MutexGuard<'static, T>,
+    _lock: Arc<Mutex<T>>,
+}
+impl<T> OwnedMutexGuard<T> {
+    pub fn lock (lock: Arc<Mutex<T>>) -> Result<Self, ResolveError> {
+        let guard = lock.lock().map_err(|_| ResolveError::Poisoned)?;
+        let guard = unsafe { mem::transmute::<MutexGuard<'_, T>, MutexGuard<'static, T>>(guard) };
+        Ok(OwnedMutexGuard{guard, _lock: lock})
+    }
+}
+impl<T> Deref for OwnedMutexGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+impl<T> DerefMut for OwnedMutexGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Condvar;
+use std::time::{Duration, Instant};
+use std::mem;
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use super::resolve_error::ResolveError;
+
+// A bounded pool of service instances. Instances are created lazily (up to the size of
+// the pool) and are returned to the pool when the guard which checked them out is dropped.
+pub struct Pool<T> {
+    size: usize,
+    timeout: Option<Duration>,
+    state: Mutex<PoolState<T>>,
+    returned: Condvar,
+}
+
+struct PoolState<T> {
+    idle: Vec<T>,
+    created: usize,
+}
+
+impl<T> Pool<T> {
+    // A timeout of None means that checking out an instance will wait indefinitely.
+    // Panics if size is 0, as checking an instance out of such a pool could never succeed.
+    pub fn new (size: usize, timeout: Option<Duration>) -> Self {
+        assert!(size > 0, "a pool must be able to hold at least one instance");
+        Pool{
+            size,
+            timeout,
+            state: Mutex::new(PoolState{idle: Vec::with_capacity(size), created: 0}),
+            returned: Condvar::new(),
+        }
+    }
+
+    pub fn size (&self) -> usize {
+        self.size
+    }
+
+    // Check an instance out of the pool. Idle instances are reused, otherwise a new instance
+    // is created if the pool isn't full yet, otherwise we wait for an instance to be returned.
+    pub fn checkout<F: FnOnce() -> T> (pool: &Arc<Self>, create: F) -> Result<PoolGuard<T>, ResolveError> {
+        let deadline = pool.timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = pool.state.lock().map_err(|_| ResolveError::Poisoned)?;
+
+        loop {
+            if let Some(value) = state.idle.pop() {
+                return Ok(PoolGuard{pool: pool.clone(), value: Some(value)});
+            }
+
+            if state.created < pool.size {
+                state.created += 1;
+
+                // Release the lock while creating the instance, as the factory may well
+                // resolve other services (including other instances from this pool)
+                drop(state);
+                let pending = PendingCreate(&**pool);
+                let value = create();
+                mem::forget(pending);
+                return Ok(PoolGuard{pool: pool.clone(), value: Some(value)});
+            }
+
+            state = match deadline {
+                None => pool.returned.wait(state).map_err(|_| ResolveError::Poisoned)?,
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(ResolveError::PoolTimeout);
+                    }
+                    pool.returned.wait_timeout(state, deadline - now).map_err(|_| ResolveError::Poisoned)?.0
+                },
+            };
+        }
+    }
+
+    // Give up a slot reserved for an instance which was never created
+    fn release (&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.created -= 1;
+            self.returned.notify_one();
+        }
+    }
+
+    fn checkin (&self, value: T) {
+        // If the lock is poisoned then the instance is simply dropped
+        if let Ok(mut state) = self.state.lock() {
+            state.idle.push(value);
+            self.returned.notify_one();
+        }
+    }
+}
+
+// Releases the slot reserved for an instance if the factory creating it panics, so that
+// the pool doesn't permanently shrink (and eventually block forever)
+struct PendingCreate<'a, T: 'a>(&'a Pool<T>);
+impl<'a, T> Drop for PendingCreate<'a, T> {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+// Guard for an instance checked out of a Pool, which returns the instance to the pool on drop
+pub struct PoolGuard<T> {
+    pool: Arc<Pool<T>>,
+    value: Option<T>,
+}
+impl<T> Deref for PoolGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().expect("PoolGuard used after its value was returned to the pool")
+    }
+}
+impl<T> DerefMut for PoolGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().expect("PoolGuard used after its value was returned to the pool")
+    }
+}
+impl<T> Drop for PoolGuard<T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.pool.checkin(value);
+        }
+    }
+}
@@ -8,6 +8,10 @@ pub enum ResolveError {
     MutImmutable,
     OwnedMutable,
     OwnedImmutable,
+    OwnedPooled,
+    PoolTimeout,
+    NotShared,
+    Expired,
 }
 
 impl fmt::Display for ResolveError {
@@ -19,6 +23,10 @@ impl fmt::Display for ResolveError {
             ResolveError::MutImmutable => write!(f, "Tried to get mutable reference to immutable service"),
             ResolveError::OwnedMutable => write!(f, "Tried to get owned value from mutable singleton service"),
             ResolveError::OwnedImmutable => write!(f, "Tried to get owned value from immutable singleton service"),
+            ResolveError::OwnedPooled => write!(f, "Tried to get owned value from pooled service"),
+            ResolveError::PoolTimeout => write!(f, "Timed out waiting for an instance of a pooled service"),
+            ResolveError::NotShared => write!(f, "Tried to get shared handle to a service which isn't a singleton of that kind"),
+            ResolveError::Expired => write!(f, "Tried to upgrade a weak handle to a service which has been rebound or whose container has been dropped"),
         }
         
     }
@@ -7,43 +7,143 @@ use std::sync::Arc;
 use std::ops::Deref;
 use std::ops::DerefMut;
 
+use super::handler::Access;
+use super::mapped_guard::{MappedReadGuard, MappedWriteGuard};
+use super::pool::{Pool, PoolGuard};
+use super::owned_guard::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, OwnedMutexGuard};
 use super::traits::Resolver;
+use super::weak::WeakSingleton;
 use super::resolve_error::ResolveError;
 
+// Factories which can fail to resolve the dependencies of the service they create
+type TryFactoryFn<R, T> = fn(&R) -> Result<T, ResolveError>;
+
 // Service enum which encapsulates the various different ways which services
 // can be bound to the container, and allows us to do runtime checking.
+// SingletonClone wraps one of the singleton kinds, adding owned values cloned from it.
 pub enum Service<R: Resolver, T> {
     SingletonArc(Arc<T>),
     SingletonRwLock(Arc<RwLock<T>>),
     SingletonMutex(Arc<Mutex<T>>),
+    SingletonClone(Box<Service<R, T>>, fn(&T) -> T),
     Factory(Arc<fn(&R) -> T>),
+    TryFactory(Arc<TryFactoryFn<R, T>>),
+    Pool(Arc<Pool<T>>, Arc<fn(&R) -> T>),
 }
 
 impl<R: Resolver, T> Service<R, T> {
-    pub fn immutable_ref (&self, resolver: &R) -> Result<ServiceReadGuard<T>, ResolveError> {
-        return match self {
+    pub fn immutable_ref (&self, resolver: &R) -> Result<ServiceReadGuard<'_, T>, ResolveError> {
+        match self {
             Service::SingletonArc(service)    => Ok(ServiceReadGuard::Arc(service.clone())),
             Service::SingletonRwLock(service) => service.read().map(ServiceReadGuard::RwLock).map_err(|_| ResolveError::Poisoned),
             Service::SingletonMutex(service)  => service.lock().map(ServiceReadGuard::Mutex).map_err(|_| ResolveError::Poisoned),
+            Service::SingletonClone(service, _) => service.immutable_ref(resolver),
             Service::Factory(factory)         => Ok(ServiceReadGuard::Owned(factory(resolver))),
+            Service::TryFactory(factory)      => factory(resolver).map(ServiceReadGuard::Owned),
+            Service::Pool(pool, factory)      => Pool::checkout(pool, || factory(resolver)).map(ServiceReadGuard::Pooled),
         }
     }
 
-    pub fn mutable_ref (&self, resolver: &R) -> Result<ServiceWriteGuard<T>, ResolveError> {
-        return match self {
+    pub fn mutable_ref (&self, resolver: &R) -> Result<ServiceWriteGuard<'_, T>, ResolveError> {
+        match self {
             Service::SingletonArc(_)          => Err(ResolveError::MutImmutable),
             Service::SingletonRwLock(service) => service.write().map(ServiceWriteGuard::RwLock).map_err(|_| ResolveError::Poisoned),
             Service::SingletonMutex(service)  => service.lock().map(ServiceWriteGuard::Mutex).map_err(|_| ResolveError::Poisoned),
+            Service::SingletonClone(service, _) => service.mutable_ref(resolver),
             Service::Factory(factory)         => Ok(ServiceWriteGuard::Owned(factory(resolver))),
+            Service::TryFactory(factory)      => factory(resolver).map(ServiceWriteGuard::Owned),
+            Service::Pool(pool, factory)      => Pool::checkout(pool, || factory(resolver)).map(ServiceWriteGuard::Pooled),
+        }
+    }
+
+    // As immutable_ref and mutable_ref, but with guards which hold a handle to the service's lock
+    // rather than borrowing it from the container
+    pub fn owned_read_guard (&self, resolver: &R) -> Result<OwnedReadGuard<T>, ResolveError> where T: 'static {
+        match self {
+            Service::SingletonArc(service)    => Ok(OwnedReadGuard::Arc(service.clone())),
+            Service::SingletonRwLock(service) => OwnedRwLockReadGuard::read(service.clone()).map(OwnedReadGuard::RwLock),
+            Service::SingletonMutex(service)  => OwnedMutexGuard::lock(service.clone()).map(OwnedReadGuard::Mutex),
+            Service::SingletonClone(service, _) => service.owned_read_guard(resolver),
+            Service::Factory(factory)         => Ok(OwnedReadGuard::Owned(factory(resolver))),
+            Service::TryFactory(factory)      => factory(resolver).map(OwnedReadGuard::Owned),
+            Service::Pool(pool, factory)      => Pool::checkout(pool, || factory(resolver)).map(OwnedReadGuard::Pooled),
+        }
+    }
+
+    pub fn owned_write_guard (&self, resolver: &R) -> Result<OwnedWriteGuard<T>, ResolveError> where T: 'static {
+        match self {
+            Service::SingletonArc(_)          => Err(ResolveError::MutImmutable),
+            Service::SingletonRwLock(service) => OwnedRwLockWriteGuard::write(service.clone()).map(OwnedWriteGuard::RwLock),
+            Service::SingletonMutex(service)  => OwnedMutexGuard::lock(service.clone()).map(OwnedWriteGuard::Mutex),
+            Service::SingletonClone(service, _) => service.owned_write_guard(resolver),
+            Service::Factory(factory)         => Ok(OwnedWriteGuard::Owned(factory(resolver))),
+            Service::TryFactory(factory)      => factory(resolver).map(OwnedWriteGuard::Owned),
+            Service::Pool(pool, factory)      => Pool::checkout(pool, || factory(resolver)).map(OwnedWriteGuard::Pooled),
         }
     }
 
     pub fn owned_value (&self, resolver: &R) -> Result<T, ResolveError> {
-        return match self {
+        match self {
             Service::SingletonArc(_)    => Err(ResolveError::OwnedImmutable),
             Service::SingletonRwLock(_) => Err(ResolveError::OwnedMutable),
             Service::SingletonMutex(_)  => Err(ResolveError::OwnedMutable),
+            Service::SingletonClone(service, clone) => service.immutable_ref(resolver).map(|value| clone(&value)),
             Service::Factory(factory)   => Ok(factory(resolver)),
+            Service::TryFactory(factory) => factory(resolver),
+            Service::Pool(_, _)         => Err(ResolveError::OwnedPooled),
+        }
+    }
+
+    // Check whether the service could be resolved with the given access, without resolving it
+    // (factories may still fail to resolve their own dependencies when they are called)
+    pub fn check_access (&self, access: Access) -> Result<(), ResolveError> {
+        match (self, access) {
+            (Service::SingletonClone(_, _), Access::OwnedValue) => Ok(()),
+            (Service::SingletonClone(service, _), access)       => service.check_access(access),
+            (Service::SingletonArc(_), Access::MutableRef)    => Err(ResolveError::MutImmutable),
+            (Service::SingletonArc(_), Access::OwnedValue)    => Err(ResolveError::OwnedImmutable),
+            (Service::SingletonRwLock(_), Access::OwnedValue) => Err(ResolveError::OwnedMutable),
+            (Service::SingletonMutex(_), Access::OwnedValue)  => Err(ResolveError::OwnedMutable),
+            (Service::Pool(_, _), Access::OwnedValue)         => Err(ResolveError::OwnedPooled),
+            (Service::SingletonArc(_), Access::SharedArc)         => Ok(()),
+            (Service::SingletonRwLock(_), Access::SharedRwLock)   => Ok(()),
+            (Service::SingletonMutex(_), Access::SharedMutex)     => Ok(()),
+            (_, Access::SharedArc) | (_, Access::SharedRwLock) | (_, Access::SharedMutex) => Err(ResolveError::NotShared),
+            _                                                 => Ok(()),
+        }
+    }
+
+    pub fn arc (&self) -> Result<Arc<T>, ResolveError> {
+        match self {
+            Service::SingletonArc(service)      => Ok(service.clone()),
+            Service::SingletonClone(service, _) => service.arc(),
+            _                                   => Err(ResolveError::NotShared),
+        }
+    }
+
+    pub fn rwlock_arc (&self) -> Result<Arc<RwLock<T>>, ResolveError> {
+        match self {
+            Service::SingletonRwLock(service)   => Ok(service.clone()),
+            Service::SingletonClone(service, _) => service.rwlock_arc(),
+            _                                   => Err(ResolveError::NotShared),
+        }
+    }
+
+    pub fn mutex_arc (&self) -> Result<Arc<Mutex<T>>, ResolveError> {
+        match self {
+            Service::SingletonMutex(service)    => Ok(service.clone()),
+            Service::SingletonClone(service, _) => service.mutex_arc(),
+            _                                   => Err(ResolveError::NotShared),
+        }
+    }
+
+    pub fn weak (&self) -> Result<WeakSingleton<T>, ResolveError> {
+        match self {
+            Service::SingletonArc(service)      => Ok(WeakSingleton::Arc(Arc::downgrade(service))),
+            Service::SingletonRwLock(service)   => Ok(WeakSingleton::RwLock(Arc::downgrade(service))),
+            Service::SingletonMutex(service)    => Ok(WeakSingleton::Mutex(Arc::downgrade(service))),
+            Service::SingletonClone(service, _) => service.weak(),
+            _                                   => Err(ResolveError::NotShared),
         }
     }
 }
@@ -54,17 +154,25 @@ pub enum ServiceReadGuard<'a, T: 'a> {
     Mutex(MutexGuard<'a, T>),
     Ref(&'a T),
     Owned(T),
+    Pooled(PoolGuard<T>),
+}
+impl<'a, T> ServiceReadGuard<'a, T> {
+    // Narrow the guard to part of the service, e.g. ServiceReadGuard::map(guard, |state| &state.config)
+    pub fn map<U: ?Sized, F: FnOnce(&T) -> &U> (guard: Self, project: F) -> MappedReadGuard<Self, U> {
+        MappedReadGuard::new(guard, project)
+    }
 }
 impl<'a, T> Deref for ServiceReadGuard<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
         match self {
-            ServiceReadGuard::Arc(guard)      => &*guard,
-            ServiceReadGuard::RwLock(guard)   => &*guard,
-            ServiceReadGuard::Mutex(guard)    => &*guard,
+            ServiceReadGuard::Arc(guard)      => guard,
+            ServiceReadGuard::RwLock(guard)   => guard,
+            ServiceReadGuard::Mutex(guard)    => guard,
             ServiceReadGuard::Ref(reference) => reference,
-            ServiceReadGuard::Owned(value)    => &*value,
+            ServiceReadGuard::Owned(value)    => value,
+            ServiceReadGuard::Pooled(guard)   => guard,
         }
     }
 }
@@ -74,16 +182,23 @@ pub enum ServiceWriteGuard<'a, T: 'a> {
     Mutex(MutexGuard<'a, T>),
     Ref(&'a mut T),
     Owned(T),
+    Pooled(PoolGuard<T>),
+}
+impl<'a, T> ServiceWriteGuard<'a, T> {
+    pub fn map<U: ?Sized, F: FnOnce(&mut T) -> &mut U> (guard: Self, project: F) -> MappedWriteGuard<Self, U> {
+        MappedWriteGuard::new(guard, project)
+    }
 }
 impl<'a, T> Deref for ServiceWriteGuard<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
         match self {
-            ServiceWriteGuard::RwLock(guard)  => &*guard,
-            ServiceWriteGuard::Mutex(guard)   => &*guard,
+            ServiceWriteGuard::RwLock(guard)  => guard,
+            ServiceWriteGuard::Mutex(guard)   => guard,
             ServiceWriteGuard::Ref(reference) => reference,
-            ServiceWriteGuard::Owned(value)   => &*value,
+            ServiceWriteGuard::Owned(value)   => value,
+            ServiceWriteGuard::Pooled(guard)  => guard,
         }
     }
 }
@@ -94,6 +209,69 @@ impl<'a, T: 'a> DerefMut for ServiceWriteGuard<'a, T> {
             ServiceWriteGuard::Mutex(guard)   => &mut *guard,
             ServiceWriteGuard::Ref(reference) => reference,
             ServiceWriteGuard::Owned(value)   => &mut *value,
+            ServiceWriteGuard::Pooled(guard)  => &mut *guard,
         }
     }
-}
\ No newline at end of file
+}
+
+// Guards like ServiceReadGuard and ServiceWriteGuard which don't borrow from the container,
+// so that they can outlive it. Like std's lock guards they aren't Send (see owned_guard.rs).
+pub enum OwnedReadGuard<T: 'static> {
+    Arc(Arc<T>),
+    RwLock(OwnedRwLockReadGuard<T>),
+    Mutex(OwnedMutexGuard<T>),
+    Owned(T),
+    Pooled(PoolGuard<T>),
+}
+impl<T> OwnedReadGuard<T> {
+    pub fn map<U: ?Sized, F: FnOnce(&T) -> &U> (guard: Self, project: F) -> MappedReadGuard<Self, U> {
+        MappedReadGuard::new(guard, project)
+    }
+}
+impl<T> Deref for OwnedReadGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            OwnedReadGuard::Arc(guard)    => guard,
+            OwnedReadGuard::RwLock(guard) => guard,
+            OwnedReadGuard::Mutex(guard)  => guard,
+            OwnedReadGuard::Owned(value)  => value,
+            OwnedReadGuard::Pooled(guard) => guard,
+        }
+    }
+}
+
+pub enum OwnedWriteGuard<T: 'static> {
+    RwLock(OwnedRwLockWriteGuard<T>),
+    Mutex(OwnedMutexGuard<T>),
+    Owned(T),
+    Pooled(PoolGuard<T>),
+}
+impl<T> OwnedWriteGuard<T> {
+    pub fn map<U: ?Sized, F: FnOnce(&mut T) -> &mut U> (guard: Self, project: F) -> MappedWriteGuard<Self, U> {
+        MappedWriteGuard::new(guard, project)
+    }
+}
+impl<T> Deref for OwnedWriteGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            OwnedWriteGuard::RwLock(guard) => guard,
+            OwnedWriteGuard::Mutex(guard)  => guard,
+            OwnedWriteGuard::Owned(value)  => value,
+            OwnedWriteGuard::Pooled(guard) => guard,
+        }
+    }
+}
+impl<T> DerefMut for OwnedWriteGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        match self {
+            OwnedWriteGuard::RwLock(guard) => &mut *guard,
+            OwnedWriteGuard::Mutex(guard)  => &mut *guard,
+            OwnedWriteGuard::Owned(value)  => &mut *value,
+            OwnedWriteGuard::Pooled(guard) => &mut *guard,
+        }
+    }
+}
@@ -1,12 +1,65 @@
 
-use super::service::{ServiceReadGuard, ServiceWriteGuard};
+use std::sync::{Arc, Mutex, RwLock};
+
+use super::container::ServiceContainer;
+use super::service::{ServiceReadGuard, ServiceWriteGuard, OwnedReadGuard, OwnedWriteGuard};
+use super::resolve_error::ResolveError;
 
 pub trait Resolver {
     type Error;
 
-    fn resolve_immutable_ref<S: 'static>(&self) -> Result<ServiceReadGuard<S>, Self::Error>;
-    fn resolve_mutable_ref<S: 'static>(&self) -> Result<ServiceWriteGuard<S>, Self::Error>;
+    fn resolve_immutable_ref<S: 'static>(&self) -> Result<ServiceReadGuard<'_, S>, Self::Error>;
+    fn resolve_mutable_ref<S: 'static>(&self) -> Result<ServiceWriteGuard<'_, S>, Self::Error>;
     fn resolve_owned_value<S: 'static>(&self) -> Result<S, Self::Error>;
+
+    // Whether an error from the methods above means that the service isn't bound
+    fn is_not_bound(error: &Self::Error) -> bool;
+
+    // As above, but resolving to None rather than an error if the service isn't bound.
+    // By default these turn the errors recognised by is_not_bound into None, so resolvers
+    // which can tell whether a service is bound without resolving it may override them.
+    fn resolve_optional_immutable_ref<S: 'static>(&self) -> Result<Option<ServiceReadGuard<'_, S>>, Self::Error> {
+        optional(self.resolve_immutable_ref::<S>(), Self::is_not_bound)
+    }
+    fn resolve_optional_mutable_ref<S: 'static>(&self) -> Result<Option<ServiceWriteGuard<'_, S>>, Self::Error> {
+        optional(self.resolve_mutable_ref::<S>(), Self::is_not_bound)
+    }
+    fn resolve_optional_owned_value<S: 'static>(&self) -> Result<Option<S>, Self::Error> {
+        optional(self.resolve_owned_value::<S>(), Self::is_not_bound)
+    }
+}
+
+fn optional<T, E>(result: Result<T, E>, is_not_bound: fn(&E) -> bool) -> Result<Option<T>, E> {
+    match result {
+        Ok(service) => Ok(Some(service)),
+        Err(ref error) if is_not_bound(error) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+// Resolvers which can also hand out handles to services that don't borrow from the resolver.
+// #[inject] requires this of resolvers for functions taking Arc or owned guard arguments.
+pub trait SharedResolver: Resolver {
+
+    // Shared handles to singleton services, which keep the service alive after the resolver
+    // (or any guard borrowed from it) is gone, e.g. for moving into a spawned thread
+    fn resolve_arc<S: 'static>(&self) -> Result<Arc<S>, Self::Error>;
+    fn resolve_rwlock_arc<S: 'static>(&self) -> Result<Arc<RwLock<S>>, Self::Error>;
+    fn resolve_mutex_arc<S: 'static>(&self) -> Result<Arc<Mutex<S>>, Self::Error>;
+
+    // As resolve_immutable_ref and resolve_mutable_ref, but with guards which are 'static rather
+    // than borrowing from the resolver, e.g. for returning from a handler inside a boxed future
+    fn resolve_owned_read_guard<S: 'static>(&self) -> Result<OwnedReadGuard<S>, Self::Error>;
+    fn resolve_owned_write_guard<S: 'static>(&self) -> Result<OwnedWriteGuard<S>, Self::Error>;
+
+    // As above, but resolving to None rather than an error if the service isn't bound
+    // (by default using is_not_bound, as for Resolver's optional methods)
+    fn resolve_optional_owned_read_guard<S: 'static>(&self) -> Result<Option<OwnedReadGuard<S>>, Self::Error> {
+        optional(self.resolve_owned_read_guard::<S>(), Self::is_not_bound)
+    }
+    fn resolve_optional_owned_write_guard<S: 'static>(&self) -> Result<Option<OwnedWriteGuard<S>>, Self::Error> {
+        optional(self.resolve_owned_write_guard::<S>(), Self::is_not_bound)
+    }
 }
 
 pub trait Inject<Ret, R: Resolver> {
@@ -21,4 +74,29 @@ impl<Ret, R: Resolver, T: Fn(&R) -> Result<Ret, R::Error>> Inject<Ret, R> for T
     fn inject(&self, resolver: &R) -> Result<Self::Return, R::Error> {
         self(resolver)
     }
-}
\ No newline at end of file
+}
+
+// Types which produce themselves from a resolver however they like, rather than being resolved
+// as a service, e.g. from a request's headers or from part of a bound config. #[inject] calls
+// from_resolver for arguments marked #[from_resolver].
+pub trait FromResolver<R: Resolver>: Sized {
+    fn from_resolver(resolver: &R) -> Result<Self, R::Error>;
+}
+
+// The results of handlers, which #[inject(flatten)] uses to name the success and error types
+// of results whose types are aliased (e.g. io::Result<T>)
+pub trait HandlerResult {
+    type Ok;
+    type Error;
+}
+
+impl<T, E> HandlerResult for Result<T, E> {
+    type Ok = T;
+    type Error = E;
+}
+
+// Types which know how to construct themselves from the services in a container.
+// Usually implemented with #[derive(Injectable)] and bound with bind_injectable.
+pub trait Injectable: Sized {
+    fn inject_new(container: &ServiceContainer) -> Result<Self, ResolveError>;
+}
@@ -0,0 +1,55 @@
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::sync::Weak;
+
+use super::owned_guard::{OwnedRwLockReadGuard, OwnedMutexGuard};
+use super::resolve_error::ResolveError;
+use super::service::OwnedReadGuard;
+
+// A weak reference to a singleton service, of whichever kind it was bound as
+pub enum WeakSingleton<T> {
+    Arc(Weak<T>),
+    RwLock(Weak<RwLock<T>>),
+    Mutex(Weak<Mutex<T>>),
+}
+impl<T> Clone for WeakSingleton<T> {
+    fn clone(&self) -> Self {
+        match self {
+            WeakSingleton::Arc(service)    => WeakSingleton::Arc(service.clone()),
+            WeakSingleton::RwLock(service) => WeakSingleton::RwLock(service.clone()),
+            WeakSingleton::Mutex(service)  => WeakSingleton::Mutex(service.clone()),
+        }
+    }
+}
+
+// A handle to a singleton service which doesn't keep it alive, e.g. for caches and observers.
+// It is tied to the binding it was resolved from as well as to the service, so upgrading fails
+// with ResolveError::Expired once the service is rebound or the container is dropped (even if
+// something else is still keeping the old service alive).
+pub struct WeakService<T> {
+    binding: Weak<()>,
+    service: WeakSingleton<T>,
+}
+impl<T: 'static> WeakService<T> {
+    pub fn new (binding: Weak<()>, service: WeakSingleton<T>) -> Self {
+        WeakService{binding, service}
+    }
+
+    // Upgrade to an owned read guard (which, for a singleton bound with a lock, holds the
+    // lock until it is dropped and so must be dropped on the thread which upgraded it)
+    pub fn upgrade (&self) -> Result<OwnedReadGuard<T>, ResolveError> {
+        if self.binding.upgrade().is_none() {
+            return Err(ResolveError::Expired);
+        }
+        match &self.service {
+            WeakSingleton::Arc(service)    => service.upgrade().map(OwnedReadGuard::Arc).ok_or(ResolveError::Expired),
+            WeakSingleton::RwLock(service) => OwnedRwLockReadGuard::read(service.upgrade().ok_or(ResolveError::Expired)?).map(OwnedReadGuard::RwLock),
+            WeakSingleton::Mutex(service)  => OwnedMutexGuard::lock(service.upgrade().ok_or(ResolveError::Expired)?).map(OwnedReadGuard::Mutex),
+        }
+    }
+}
+impl<T> Clone for WeakService<T> {
+    fn clone(&self) -> Self {
+        WeakService{binding: self.binding.clone(), service: self.service.clone()}
+    }
+}
@@ -0,0 +1,45 @@
+extern crate rustdi;
+
+use std::sync::Arc;
+use rustdi::{Resolver, ResolveError, ServiceContainer};
+
+struct Client(&'static str);
+
+struct Bucket {
+    client: &'static str,
+    name: String,
+}
+
+fn bucket(c: &ServiceContainer, name: &'static str) -> Result<Bucket, ResolveError> {
+    Ok(Bucket{client: c.resolve_immutable_ref::<Client>()?.0, name: name.into()})
+}
+
+#[test]
+fn factory_receives_arguments() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Client("s3")));
+    c.bind_factory_with_args(bucket);
+
+    let bucket = c.resolve_with::<Bucket, _>("images").unwrap();
+    assert_eq!(bucket.client, "s3");
+    assert_eq!(bucket.name, "images");
+}
+
+#[test]
+fn factory_failure_is_returned() {
+    let mut c = ServiceContainer::new();
+    c.bind_factory_with_args(bucket);
+
+    let result = c.resolve_with::<Bucket, _>("images").map(|_| ());
+    assert!(matches!(result, Err(ResolveError::NonExist)));
+}
+
+#[test]
+fn named_factory_is_only_resolved_by_name() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Client("s3")));
+    c.bind_named("archive", |c| c.bind_factory_with_args(bucket));
+
+    assert!(c.resolve_with::<Bucket, &'static str>("images").is_err());
+    assert_eq!(c.resolve_named_with::<Bucket, _>("archive", "images").unwrap().name, "images");
+}
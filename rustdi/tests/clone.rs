@@ -0,0 +1,34 @@
+extern crate rustdi;
+
+use std::sync::{Arc, RwLock};
+use rustdi::{Resolver, ServiceContainer, SharedResolver};
+
+#[derive(Clone)]
+struct Settings {
+    name: String,
+}
+
+#[test]
+fn rwlock_singleton_resolves_to_a_clone_of_its_current_value() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_clone(Arc::new(RwLock::new(Settings{name: "before".into()})));
+
+    let snapshot = c.resolve_owned_value::<Settings>().unwrap();
+    c.resolve_mutable_ref::<Settings>().unwrap().name = "after".into();
+
+    assert_eq!(snapshot.name, "before");
+    assert_eq!(c.resolve_owned_value::<Settings>().unwrap().name, "after");
+    assert!(c.resolve_rwlock_arc::<Settings>().is_ok());
+}
+
+#[test]
+fn arc_singleton_resolves_to_a_clone() {
+    let mut c = ServiceContainer::new();
+    let settings = Arc::new(Settings{name: "shared".into()});
+    c.bind_singleton_arc_clone(settings.clone());
+
+    assert_eq!(c.resolve_owned_value::<Settings>().unwrap().name, "shared");
+    assert_eq!(c.resolve_immutable_ref::<Settings>().unwrap().name, "shared");
+    assert!(Arc::ptr_eq(&c.resolve_arc::<Settings>().unwrap(), &settings));
+    assert!(c.resolve_mutable_ref::<Settings>().is_err());
+}
@@ -0,0 +1,55 @@
+extern crate rustdi;
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use rustdi::{GenericFactory, Resolver, ServiceContainer};
+
+struct Db(&'static str);
+
+struct User;
+struct Order;
+struct Invoice;
+
+trait Model {
+    const TABLE: &'static str;
+}
+impl Model for User { const TABLE: &'static str = "users"; }
+impl Model for Order { const TABLE: &'static str = "orders"; }
+impl Model for Invoice { const TABLE: &'static str = "invoices"; }
+
+struct Repository<T> {
+    location: String,
+    _model: PhantomData<fn() -> T>,
+}
+
+struct RepositoryFactory;
+
+impl<T: Model + 'static> GenericFactory<T> for RepositoryFactory {
+    type Service = Repository<T>;
+
+    fn create(container: &ServiceContainer) -> Repository<T> {
+        let db = container.resolve_immutable_ref::<Db>().unwrap().0;
+        Repository{location: format!("{}/{}", db, T::TABLE), _model: PhantomData}
+    }
+}
+
+#[test]
+fn generic_factory_is_bound_for_each_type() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Db("postgres")));
+    c.bind_generic::<RepositoryFactory, (User, Order)>();
+
+    assert_eq!(c.resolve_owned_value::<Repository<User>>().unwrap().location, "postgres/users");
+    assert_eq!(c.resolve_immutable_ref::<Repository<Order>>().unwrap().location, "postgres/orders");
+    assert!(c.resolve_owned_value::<Repository<Invoice>>().is_err());
+}
+
+#[test]
+fn generic_factory_instances_are_not_shared() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Db("sqlite")));
+    c.bind_generic::<RepositoryFactory, (Invoice,)>();
+
+    c.resolve_mutable_ref::<Repository<Invoice>>().unwrap().location.push_str("_archive");
+    assert_eq!(c.resolve_owned_value::<Repository<Invoice>>().unwrap().location, "sqlite/invoices");
+}
@@ -0,0 +1,135 @@
+extern crate rustdi;
+
+use std::sync::{Arc, RwLock, TryLockError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::panic;
+use std::time::Duration;
+use rustdi::{MappedReadGuard, MappedWriteGuard, OwnedReadGuard, OwnedWriteGuard, Resolver, ResolveError, ServiceContainer,
+             ServiceReadGuard, ServiceWriteGuard, SharedResolver};
+
+struct Config {
+    name: String,
+    ports: Vec<u16>,
+}
+
+fn config() -> Config {
+    Config{name: "frogs".into(), ports: vec![80, 443]}
+}
+
+#[test]
+fn maps_owned_guard_and_drops_service_once() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+    struct Tracked(Config);
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut c = ServiceContainer::new();
+    c.bind_factory(|_| Tracked(config()));
+    {
+        let guard = c.resolve_immutable_ref::<Tracked>().unwrap();
+        assert!(matches!(guard, ServiceReadGuard::Owned(_)));
+        let name = ServiceReadGuard::map(guard, |tracked| tracked.0.name.as_str());
+        let first = MappedReadGuard::map(name, |name| &name[..1]);
+        assert_eq!(&*first, "f");
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 0);
+    }
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn maps_arc_guard() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(config()));
+    let guard = c.resolve_immutable_ref::<Config>().unwrap();
+    assert!(matches!(guard, ServiceReadGuard::Arc(_)));
+    let ports = ServiceReadGuard::map(guard, |config| &config.ports[..]);
+    assert_eq!(&*ports, &[80, 443]);
+}
+
+#[test]
+fn maps_ref_guard() {
+    let config = config();
+    let ports = ServiceReadGuard::map(ServiceReadGuard::Ref(&config), |config| &config.ports);
+    let last = MappedReadGuard::map(ports, |ports| &ports[1]);
+    assert_eq!(*last, 443);
+
+    let mut config = self::config();
+    {
+        let mut name = ServiceWriteGuard::map(ServiceWriteGuard::Ref(&mut config), |config| &mut config.name);
+        name.push_str(" and toads");
+    }
+    assert_eq!(config.name, "frogs and toads");
+}
+
+#[test]
+fn maps_pooled_guard_and_returns_instance() {
+    let mut c = ServiceContainer::new();
+    c.bind_pool_with_timeout(1, Duration::from_millis(50), |_| config());
+    {
+        let guard = c.resolve_mutable_ref::<Config>().unwrap();
+        assert!(matches!(guard, ServiceWriteGuard::Pooled(_)));
+        let mut ports = ServiceWriteGuard::map(guard, |config| &mut config.ports);
+        ports.push(8080);
+        assert!(c.resolve_immutable_ref::<Config>().is_err());
+    }
+    assert_eq!(c.resolve_immutable_ref::<Config>().unwrap().ports, vec![80, 443, 8080]);
+}
+
+#[test]
+fn nested_write_maps_keep_lock_until_dropped() {
+    let lock = Arc::new(RwLock::new(config()));
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_rwlock(lock.clone());
+    {
+        let ports = OwnedWriteGuard::map(c.resolve_owned_write_guard::<Config>().unwrap(), |config| &mut config.ports);
+        let mut first = MappedWriteGuard::map(ports, |ports| &mut ports[0]);
+        *first = 8080;
+        let mut first = MappedWriteGuard::map(first, |port| port);
+        *first += 1;
+        assert!(lock.try_read().is_err());
+    }
+    assert!(!lock.is_poisoned());
+    assert_eq!(c.resolve_immutable_ref::<Config>().unwrap().ports, vec![8081, 443]);
+}
+
+#[test]
+fn mapped_owned_guards_outlive_the_container() {
+    let name = {
+        let mut c = ServiceContainer::new();
+        c.bind_singleton_rwlock(Arc::new(RwLock::new(config())));
+        OwnedReadGuard::map(c.resolve_owned_read_guard::<Config>().unwrap(), |config| config.name.as_str())
+    };
+    assert_eq!(&*name, "frogs");
+}
+
+#[test]
+fn panicking_read_projection_releases_the_lock() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(config())));
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        ServiceReadGuard::map(c.resolve_immutable_ref::<Config>().unwrap(), |config| &config.ports[2]);
+    }));
+    assert!(result.is_err());
+
+    // (a read guard doesn't poison the lock, so it can be written again)
+    c.resolve_mutable_ref::<Config>().unwrap().ports.push(8080);
+    assert_eq!(c.resolve_immutable_ref::<Config>().unwrap().ports, vec![80, 443, 8080]);
+}
+
+#[test]
+fn panicking_write_projection_releases_the_lock() {
+    let lock = Arc::new(RwLock::new(config()));
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_rwlock(lock.clone());
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        ServiceWriteGuard::map(c.resolve_mutable_ref::<Config>().unwrap(), |config| &mut config.ports[2]);
+    }));
+    assert!(result.is_err());
+
+    // The write guard was dropped while panicking, so the lock is released but poisoned
+    assert!(matches!(c.resolve_immutable_ref::<Config>().map(|_| ()), Err(ResolveError::Poisoned)));
+    assert!(lock.try_write().is_err_and(|error| matches!(error, TryLockError::Poisoned(_))));
+}
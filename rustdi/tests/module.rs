@@ -0,0 +1,65 @@
+extern crate rustdi;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rustdi::{Module, Resolver, ServiceContainer};
+
+struct Url(&'static str);
+
+struct DbModule {
+    url: &'static str,
+}
+
+impl Module for DbModule {
+    fn configure(&self, c: &mut ServiceContainer) {
+        c.bind_singleton_arc(Arc::new(Url(self.url)));
+    }
+}
+
+#[test]
+fn module_of_the_same_type_is_only_configured_once() {
+    let mut c = ServiceContainer::new();
+    assert!(!c.has_module::<DbModule>());
+    c.install(DbModule{url: "postgres://primary"});
+    c.install(DbModule{url: "postgres://replica"});
+
+    assert!(c.has_module::<DbModule>());
+    assert_eq!(c.resolve_immutable_ref::<Url>().unwrap().0, "postgres://primary");
+}
+
+#[test]
+fn nested_modules_are_installed_once() {
+    static CONFIGURED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+    impl Module for Counted {
+        fn configure(&self, c: &mut ServiceContainer) {
+            CONFIGURED.fetch_add(1, Ordering::SeqCst);
+            c.install(DbModule{url: "postgres://nested"});
+        }
+    }
+
+    struct Api;
+    impl Module for Api {
+        fn configure(&self, c: &mut ServiceContainer) {
+            c.install(Counted);
+        }
+    }
+
+    // Installs itself from configure, which must not recurse
+    struct Cyclic;
+    impl Module for Cyclic {
+        fn configure(&self, c: &mut ServiceContainer) {
+            c.install(Api);
+            c.install(Cyclic);
+        }
+    }
+
+    let mut c = ServiceContainer::new();
+    c.install(Cyclic);
+    c.install(Counted);
+
+    assert!(c.has_module::<Api>() && c.has_module::<Counted>() && c.has_module::<DbModule>());
+    assert_eq!(CONFIGURED.load(Ordering::SeqCst), 1);
+    assert_eq!(c.resolve_immutable_ref::<Url>().unwrap().0, "postgres://nested");
+}
@@ -0,0 +1,87 @@
+extern crate rustdi;
+
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::mpsc;
+use std::panic;
+use std::thread;
+use std::time::Duration;
+use rustdi::{OwnedReadGuard, OwnedWriteGuard, Resolver, ResolveError, ServiceContainer, SharedResolver};
+
+struct State {
+    count: u32,
+}
+
+fn container() -> ServiceContainer {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(State{count: 0})));
+    c.bind_singleton_mutex(Arc::new(Mutex::new(0u32)));
+    c.bind_singleton_arc(Arc::new(String::from("config")));
+    c.bind_factory(|_| 1.5f64);
+    c
+}
+
+#[test]
+fn owned_guards_outlive_the_container() {
+    let (state, count, config, value) = {
+        let c = container();
+        let state = c.resolve_owned_read_guard::<State>().unwrap();
+        let count = c.resolve_owned_write_guard::<u32>().unwrap();
+        let config = c.resolve_owned_read_guard::<String>().unwrap();
+        let value = c.resolve_owned_write_guard::<f64>().unwrap();
+        (state, count, config, value)
+    };
+    assert!(matches!(state, OwnedReadGuard::RwLock(_)));
+    assert!(matches!(count, OwnedWriteGuard::Mutex(_)));
+    assert!(matches!(config, OwnedReadGuard::Arc(_)));
+    assert!(matches!(value, OwnedWriteGuard::Owned(_)));
+    assert_eq!((state.count, *count, config.as_str(), *value), (0, 0, "config", 1.5));
+}
+
+#[test]
+fn owned_write_guards_change_the_service() {
+    let c = container();
+    c.resolve_owned_write_guard::<State>().unwrap().count += 1;
+    *c.resolve_owned_write_guard::<u32>().unwrap() += 2;
+    assert_eq!(c.resolve_immutable_ref::<State>().unwrap().count, 1);
+    assert_eq!(*c.resolve_immutable_ref::<u32>().unwrap(), 2);
+}
+
+#[test]
+fn owned_write_guards_of_arc_singletons_are_refused() {
+    let c = container();
+    assert!(matches!(c.resolve_owned_write_guard::<String>().map(|_| ()), Err(ResolveError::MutImmutable)));
+}
+
+#[test]
+fn write_guard_holds_the_lock_until_dropped() {
+    let c = Arc::new(container());
+    let writer = c.resolve_owned_write_guard::<State>().unwrap();
+
+    let (sender, receiver) = mpsc::channel();
+    let reader = {
+        let c = c.clone();
+        thread::spawn(move || {
+            let state = c.resolve_immutable_ref::<State>().unwrap();
+            sender.send(state.count).unwrap();
+        })
+    };
+
+    // The reader waits for the writer to be dropped
+    assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+    drop(writer);
+    assert_eq!(receiver.recv().unwrap(), 0);
+    reader.join().unwrap();
+}
+
+#[test]
+fn panicking_writer_poisons_the_lock() {
+    let c = container();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _state = c.resolve_owned_write_guard::<State>().unwrap();
+        panic!("failed while writing");
+    }));
+    assert!(result.is_err());
+
+    let result = c.resolve_owned_read_guard::<State>().map(|_| ());
+    assert!(matches!(result, Err(ResolveError::Poisoned)));
+}
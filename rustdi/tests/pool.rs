@@ -0,0 +1,83 @@
+extern crate rustdi;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::panic;
+use std::thread;
+use std::time::Duration;
+use rustdi::{Resolver, ResolveError, ServiceContainer};
+
+struct Connection(usize);
+
+#[test]
+fn instances_are_returned_and_reused() {
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut c = ServiceContainer::new();
+    c.bind_pool(2, |_| Connection(CREATED.fetch_add(1, Ordering::SeqCst)));
+
+    {
+        let first = c.resolve_immutable_ref::<Connection>().unwrap();
+        let second = c.resolve_mutable_ref::<Connection>().unwrap();
+        assert_ne!(first.0, second.0);
+    }
+
+    // Both instances are back in the pool, so no more are created
+    for _ in 0..4 {
+        let _conn = c.resolve_immutable_ref::<Connection>().unwrap();
+    }
+    assert_eq!(CREATED.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn checkout_waits_for_an_instance_to_be_returned() {
+    let mut c = ServiceContainer::new();
+    c.bind_pool(1, |_| Connection(0));
+    let c = Arc::new(c);
+
+    let held = c.resolve_mutable_ref::<Connection>().unwrap();
+    let waiter = {
+        let c = c.clone();
+        thread::spawn(move || c.resolve_immutable_ref::<Connection>().map(|conn| conn.0).map_err(|_| ()))
+    };
+
+    thread::sleep(Duration::from_millis(50));
+    drop(held);
+    assert_eq!(waiter.join().unwrap(), Ok(0));
+}
+
+#[test]
+fn checkout_times_out_when_the_pool_is_exhausted() {
+    let mut c = ServiceContainer::new();
+    c.bind_pool_with_timeout(1, Duration::from_millis(20), |_| Connection(0));
+
+    let _held = c.resolve_immutable_ref::<Connection>().unwrap();
+    let result = c.resolve_immutable_ref::<Connection>().map(|_| ());
+    assert!(matches!(result, Err(ResolveError::PoolTimeout)));
+}
+
+#[test]
+fn panicking_factory_does_not_shrink_the_pool() {
+    static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+    let mut c = ServiceContainer::new();
+    c.bind_pool_with_timeout(1, Duration::from_millis(20), |_| {
+        if ATTEMPTS.fetch_add(1, Ordering::SeqCst) == 0 {
+            panic!("failed to connect");
+        }
+        Connection(1)
+    });
+
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _conn = c.resolve_immutable_ref::<Connection>();
+    }));
+    assert!(result.is_err());
+    assert_eq!(c.resolve_immutable_ref::<Connection>().unwrap().0, 1);
+}
+
+#[test]
+#[should_panic(expected = "at least one instance")]
+fn empty_pool_is_rejected_at_bind_time() {
+    let mut c = ServiceContainer::new();
+    c.bind_pool(0, |_| Connection(0));
+}
@@ -0,0 +1,54 @@
+extern crate rustdi;
+
+use std::sync::Arc;
+use rustdi::{Resolver, ServiceContainer};
+
+struct Database(&'static str);
+
+fn resolve_database(c: &ServiceContainer) -> &'static str {
+    c.resolve_immutable_ref::<Database>().unwrap().0
+}
+
+#[test]
+fn without_profile_only_defaults_are_bound() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Database("default")));
+    c.when_profile("test", |c| c.bind_singleton_arc(Arc::new(Database("test"))));
+    assert_eq!(c.profile(), None);
+    assert_eq!(resolve_database(&c), "default");
+}
+
+#[test]
+fn inactive_profile_is_ignored() {
+    let mut c = ServiceContainer::with_profile("production");
+    c.when_profile("test", |c| c.bind_singleton_arc(Arc::new(Database("test"))));
+    c.bind_singleton_arc(Arc::new(Database("default")));
+    assert_eq!(c.profile(), Some("production"));
+    assert_eq!(resolve_database(&c), "default");
+}
+
+#[test]
+fn profile_binding_overrides_earlier_default() {
+    let mut c = ServiceContainer::with_profile("test");
+    c.bind_singleton_arc(Arc::new(Database("default")));
+    c.when_profile("test", |c| c.bind_singleton_arc(Arc::new(Database("test"))));
+    assert_eq!(resolve_database(&c), "test");
+}
+
+#[test]
+fn profile_binding_overrides_later_default() {
+    let mut c = ServiceContainer::with_profile("test");
+    c.when_profile("test", |c| c.bind_singleton_arc(Arc::new(Database("test"))));
+    c.bind_singleton_arc(Arc::new(Database("default")));
+    assert_eq!(resolve_database(&c), "test");
+}
+
+#[test]
+fn profile_binding_overrides_named_default() {
+    let mut c = ServiceContainer::with_profile("test");
+    c.when_profile("test", |c| c.bind_named("primary", |c| c.bind_singleton_arc(Arc::new(Database("test")))));
+    c.bind_named("primary", |c| c.bind_singleton_arc(Arc::new(Database("default"))));
+    c.bind_named("replica", |c| c.bind_singleton_arc(Arc::new(Database("replica"))));
+    assert_eq!(c.resolve_named_arc::<Database>("primary").unwrap().0, "test");
+    assert_eq!(c.resolve_named_arc::<Database>("replica").unwrap().0, "replica");
+}
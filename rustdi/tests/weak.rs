@@ -0,0 +1,91 @@
+extern crate rustdi;
+
+use std::sync::{Arc, Mutex, RwLock};
+use rustdi::{OwnedReadGuard, ResolveError, ServiceContainer};
+
+struct Cache {
+    entries: usize,
+}
+
+#[test]
+fn weak_handles_upgrade_while_bound() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Cache{entries: 1}));
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(2usize)));
+    c.bind_singleton_mutex(Arc::new(Mutex::new(3u32)));
+
+    let cache = c.resolve_weak::<Cache>().unwrap().upgrade().unwrap();
+    assert!(matches!(cache, OwnedReadGuard::Arc(_)));
+    assert_eq!(cache.entries, 1);
+    let size = c.resolve_weak::<usize>().unwrap().upgrade().unwrap();
+    assert!(matches!(size, OwnedReadGuard::RwLock(_)));
+    assert_eq!(*size, 2);
+    let count = c.resolve_weak::<u32>().unwrap().upgrade().unwrap();
+    assert!(matches!(count, OwnedReadGuard::Mutex(_)));
+    assert_eq!(*count, 3);
+}
+
+#[test]
+fn weak_handles_dont_keep_services_alive() {
+    let cache = Arc::new(Cache{entries: 1});
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(cache.clone());
+    let weak = c.resolve_weak::<Cache>().unwrap();
+    assert_eq!(Arc::strong_count(&cache), 2);
+    drop(c);
+    assert_eq!(Arc::strong_count(&cache), 1);
+    drop(weak);
+}
+
+#[test]
+fn weak_handles_expire_when_rebound() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(Cache{entries: 1})));
+    let weak = c.resolve_weak::<Cache>().unwrap();
+
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(Cache{entries: 2})));
+    assert!(matches!(weak.upgrade().map(|_| ()), Err(ResolveError::Expired)));
+    assert_eq!(c.resolve_weak::<Cache>().unwrap().upgrade().unwrap().entries, 2);
+}
+
+#[test]
+fn weak_handles_expire_when_rebound_even_if_service_is_alive() {
+    let cache = Arc::new(Cache{entries: 1});
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(cache.clone());
+    let weak = c.resolve_weak::<Cache>().unwrap();
+
+    c.bind_singleton_arc(cache);
+    assert!(matches!(weak.upgrade().map(|_| ()), Err(ResolveError::Expired)));
+}
+
+#[test]
+fn weak_handles_expire_when_container_is_dropped() {
+    let weak = {
+        let mut c = ServiceContainer::new();
+        c.bind_singleton_mutex(Arc::new(Mutex::new(Cache{entries: 1})));
+        c.resolve_weak::<Cache>().unwrap()
+    };
+    assert!(matches!(weak.clone().upgrade().map(|_| ()), Err(ResolveError::Expired)));
+}
+
+#[test]
+fn named_weak_handles_expire_when_their_binding_is_replaced() {
+    let mut c = ServiceContainer::new();
+    c.bind_named("primary", |c| c.bind_singleton_arc(Arc::new(Cache{entries: 1})));
+    c.bind_named("replica", |c| c.bind_singleton_arc(Arc::new(Cache{entries: 2})));
+    let primary = c.resolve_named_weak::<Cache>("primary").unwrap();
+    let replica = c.resolve_named_weak::<Cache>("replica").unwrap();
+    assert!(matches!(c.resolve_weak::<Cache>().map(|_| ()), Err(ResolveError::NonExist)));
+
+    c.bind_named("primary", |c| c.bind_singleton_arc(Arc::new(Cache{entries: 3})));
+    assert!(matches!(primary.upgrade().map(|_| ()), Err(ResolveError::Expired)));
+    assert_eq!(replica.upgrade().unwrap().entries, 2);
+}
+
+#[test]
+fn weak_handles_to_non_singletons_are_refused() {
+    let mut c = ServiceContainer::new();
+    c.bind_factory(|_| Cache{entries: 0});
+    assert!(matches!(c.resolve_weak::<Cache>().map(|_| ()), Err(ResolveError::NotShared)));
+}
@@ -0,0 +1,296 @@
+use crate::proc_macro2::{Span, TokenStream};
+
+use syn::{FnArg, ArgCaptured, Type, TypePath, TypeReference, TypeSlice, TypeParen, TypeGroup, TypeTraitObject, TypeParamBound, TraitBound,
+          Ident, Path, PathArguments, GenericArgument};
+use syn::parse::Error;
+use syn::spanned::Spanned;
+
+#[derive(Clone, Copy)]
+pub enum ResolveType {
+    ImmutableBorrow,
+    MutableBorrow,
+    OwnedValue,
+    OptionalImmutableBorrow,
+    OptionalMutableBorrow,
+    OptionalOwnedValue,
+    SharedArc,
+    SharedRwLock,
+    SharedMutex,
+    OwnedReadGuard,
+    OwnedWriteGuard,
+}
+
+// If a path is wrapper<T> (e.g. Option<T>) then return T
+fn wrapped_type(path: &Path, wrapper: &str) -> Option<Type> {
+    let segment = path.segments.iter().last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+            match args.args.iter().next() {
+                Some(GenericArgument::Type(ty)) => Some(ty.clone()),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+// Unsized types can't be bound to a container, so references to them are resolved from the
+// owned type which derefs to them: &str from String, &[T] from Vec<T> and &dyn Trait from
+// Box<dyn Trait + Send + Sync> (as services must be Send and Sync, so a Box<dyn Trait> can't be bound)
+fn owning_type(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::Slice(TypeSlice{ elem, .. }) => Some(parse_quote!{ Vec<#elem> }),
+        Type::TraitObject(object) => {
+            let mut object = object.clone();
+            for auto_trait in &["Send", "Sync"] {
+                if !has_trait_bound(&object, auto_trait) {
+                    let auto_trait = Ident::new(auto_trait, Span::call_site());
+                    object.bounds.push(parse_quote!{ ::std::marker::#auto_trait });
+                }
+            }
+            Some(parse_quote!{ Box<#object> })
+        },
+        Type::Path(TypePath{ qself: None, path }) if path.leading_colon.is_none() && path.segments.len() == 1 => {
+            let segment = &path.segments[0];
+            if segment.ident == "str" && segment.arguments.is_empty() { Some(parse_quote!{ String }) } else { None }
+        },
+        _ => None,
+    }
+}
+
+// Whether a trait object is already bounded by the named trait (e.g. dyn Trait + Send)
+fn has_trait_bound(object: &TypeTraitObject, name: &str) -> bool {
+    object.bounds.iter().any(|bound| match bound {
+        TypeParamBound::Trait(TraitBound{ path, .. }) => path.segments.iter().last().is_some_and(|segment| segment.ident == name),
+        _ => false,
+    })
+}
+
+// Remove any parentheses (or invisible groups) around a type
+fn ungrouped_type(ty: Type) -> Type {
+    match ty {
+        Type::Paren(TypeParen{ elem, .. }) | Type::Group(TypeGroup{ elem, .. }) => ungrouped_type(*elem),
+        ty => ty,
+    }
+}
+
+// Work out the type of service to resolve for an argument (or an Injectable struct's field) of
+// type ty, how to resolve it, and whether the resolved service is an owning type which must be
+// dereferenced once more
+pub fn service_type_and_resolve_type(ty: Type) -> Result<(Type, ResolveType, bool), Error> {
+    let span = ty.span();
+    match ungrouped_type(ty) {
+        Type::Reference(TypeReference{ mutability, elem, .. }) => {
+            let elem = ungrouped_type(*elem);
+            let (arg_type, deref_owner) = match owning_type(&elem) {
+                Some(owner) => (owner, true),
+                None        => (elem, false),
+            };
+            let arg_mutability = match &mutability {
+                Some(_) => ResolveType::MutableBorrow,
+                None    => ResolveType::ImmutableBorrow
+            };
+            Ok((arg_type, arg_mutability, deref_owner))
+        },
+        // Arc<RwLock<T>> and Arc<Mutex<T>> are shared handles to locked singletons of type T,
+        // and any other Arc<T> is a shared handle to an Arc singleton of type T
+        Type::Path(TypePath{ qself: None, path: arg_path }) if wrapped_type(&arg_path, "Arc").is_some() => {
+            let inner = ungrouped_type(wrapped_type(&arg_path, "Arc").unwrap());
+            let locked = match &inner {
+                Type::Path(TypePath{ qself: None, path }) => {
+                    wrapped_type(path, "RwLock").map(|ty| (ty, ResolveType::SharedRwLock))
+                        .or_else(|| wrapped_type(path, "Mutex").map(|ty| (ty, ResolveType::SharedMutex)))
+                },
+                _ => None,
+            };
+            let (inner_type, resolve_type) = locked.unwrap_or((inner, ResolveType::SharedArc));
+            Ok((inner_type, resolve_type, false))
+        },
+        // OwnedReadGuard<T> and OwnedWriteGuard<T> are borrows of T which don't borrow from the resolver
+        Type::Path(TypePath{ qself: None, path: arg_path }) if wrapped_type(&arg_path, "OwnedReadGuard").is_some() => {
+            Ok((wrapped_type(&arg_path, "OwnedReadGuard").unwrap(), ResolveType::OwnedReadGuard, false))
+        },
+        Type::Path(TypePath{ qself: None, path: arg_path }) if wrapped_type(&arg_path, "OwnedWriteGuard").is_some() => {
+            Ok((wrapped_type(&arg_path, "OwnedWriteGuard").unwrap(), ResolveType::OwnedWriteGuard, false))
+        },
+        Type::Path(TypePath{ qself: None, path: arg_path }) if wrapped_type(&arg_path, "Option").is_some() => {
+            let inner = wrapped_type(&arg_path, "Option").unwrap();
+            let inner_span = inner.span();
+            let (inner_type, inner_resolve_type, deref_owner) = service_type_and_resolve_type(inner)?;
+            let optional_resolve_type = match inner_resolve_type {
+                ResolveType::ImmutableBorrow => ResolveType::OptionalImmutableBorrow,
+                ResolveType::MutableBorrow   => ResolveType::OptionalMutableBorrow,
+                ResolveType::OwnedValue      => ResolveType::OptionalOwnedValue,
+                ResolveType::SharedArc | ResolveType::SharedRwLock | ResolveType::SharedMutex => {
+                    return Err(Error::new(inner_span, "optional Arc services can't be injected"));
+                },
+                ResolveType::OwnedReadGuard | ResolveType::OwnedWriteGuard => {
+                    return Err(Error::new(inner_span, "optional owned guards can't be injected"));
+                },
+                _ => return Err(Error::new(inner_span, "nested Option arguments can't be injected")),
+            };
+            Ok((inner_type, optional_resolve_type, deref_owner))
+        },
+        Type::ImplTrait(_) => Err(Error::new(span, "impl Trait arguments can't be injected here, use a type parameter instead")),
+        Type::TraitObject(_) | Type::Slice(_) => Err(Error::new(span, "unsized arguments can't be injected by value, take a reference instead")),
+        Type::Ptr(_) => Err(Error::new(span, "raw pointer arguments can't be injected, take a reference instead")),
+        Type::Infer(_) | Type::Never(_) | Type::Macro(_) | Type::Verbatim(_) => {
+            Err(Error::new(span, "the type of an injected argument must be a named type, a reference or an Option"))
+        },
+        ty => Ok((ty, ResolveType::OwnedValue, false)),
+    }
+}
+
+// An injected argument: code to resolve its value (or a guard for its value) from the resolver
+// (and the resolver method that calls, and whether that is a SharedResolver method), the binding
+// that is stored in, code to pass the bound value to the original function, and code describing
+// it as a handler dependency
+pub struct ResolvedArg {
+    pub binding: TokenStream,
+    pub resolve: TokenStream,
+    pub resolve_method: TokenStream,
+    pub shared: bool,
+    pub pass: TokenStream,
+    pub dependency: TokenStream,
+}
+
+// The trait a resolver must implement to resolve all of the given arguments
+pub fn resolver_bound(args: &[ResolvedArg]) -> TokenStream {
+    if args.iter().any(|arg| arg.shared) {
+        quote_spanned!{Span::call_site() => ::rustdi::SharedResolver}
+    } else {
+        quote_spanned!{Span::call_site() => ::rustdi::Resolver}
+    }
+}
+
+// Generate code to resolve each of a function's arguments from a resolver with requested mutability.
+// (Optional references are passed with a match rather than Option::map so that the references
+// can be coerced to the argument type, e.g. to shorten the lifetime of a trait object)
+pub fn resolve_args<I: IntoIterator<Item=FnArg>>(inputs: I) -> Result<Vec<ResolvedArg>, Error> {
+    resolve_args_with(inputs, false)
+}
+
+// As resolve_args, but with references resolved through owned guards rather than guards which
+// borrow from the resolver, so that the resolved arguments can outlive it (e.g. in a future)
+pub fn resolve_owned_args<I: IntoIterator<Item=FnArg>>(inputs: I) -> Result<Vec<ResolvedArg>, Error> {
+    resolve_args_with(inputs, true)
+}
+
+fn resolve_args_with<I: IntoIterator<Item=FnArg>>(inputs: I, owned: bool) -> Result<Vec<ResolvedArg>, Error> {
+    let resolve_types = inputs.into_iter()
+        .map(|arg| {
+            match arg {
+                FnArg::Captured(ArgCaptured{ ty, .. }) => service_type_and_resolve_type(ty),
+                FnArg::SelfRef(_) | FnArg::SelfValue(_) => Err(Error::new(arg.span(), "self can't be injected")),
+                _ => Err(Error::new(arg.span(), "injected arguments must have a type, e.g. `state: &AppState`")),
+            }
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let args = resolve_types.into_iter()
+        .enumerate()
+        .map(|(index, (arg_type, arg_mutability, deref_owner))| {
+            let ident = Ident::new(format!("__rustdi_arg{}", index).as_str(), Span::call_site());
+            let deref = if deref_owner { quote!{ ** } } else { quote!{ * } };
+            let (binding, method, pass) = match arg_mutability {
+                ResolveType::ImmutableBorrow => (
+                    quote_spanned!{Span::call_site() => #ident},
+                    quote!{ resolve_immutable_ref },
+                    quote_spanned!{Span::call_site() => &#deref #ident},
+                ),
+                ResolveType::MutableBorrow => (
+                    quote_spanned!{Span::call_site() => mut #ident},
+                    quote!{ resolve_mutable_ref },
+                    quote_spanned!{Span::call_site() => &mut #deref #ident},
+                ),
+                ResolveType::OwnedValue => (
+                    quote_spanned!{Span::call_site() => #ident},
+                    quote!{ resolve_owned_value },
+                    quote_spanned!{Span::call_site() => #ident},
+                ),
+                ResolveType::OptionalImmutableBorrow => (
+                    quote_spanned!{Span::call_site() => #ident},
+                    quote!{ resolve_optional_immutable_ref },
+                    quote_spanned!{Span::call_site() => match #ident.as_ref() { Some(s) => Some(&*#deref s), None => None }},
+                ),
+                ResolveType::OptionalMutableBorrow => (
+                    quote_spanned!{Span::call_site() => mut #ident},
+                    quote!{ resolve_optional_mutable_ref },
+                    quote_spanned!{Span::call_site() => match #ident.as_mut() { Some(s) => Some(&mut *#deref s), None => None }},
+                ),
+                ResolveType::OptionalOwnedValue => (
+                    quote_spanned!{Span::call_site() => #ident},
+                    quote!{ resolve_optional_owned_value },
+                    quote_spanned!{Span::call_site() => #ident},
+                ),
+                ResolveType::SharedArc => (
+                    quote_spanned!{Span::call_site() => #ident},
+                    quote!{ resolve_arc },
+                    quote_spanned!{Span::call_site() => #ident},
+                ),
+                ResolveType::SharedRwLock => (
+                    quote_spanned!{Span::call_site() => #ident},
+                    quote!{ resolve_rwlock_arc },
+                    quote_spanned!{Span::call_site() => #ident},
+                ),
+                ResolveType::SharedMutex => (
+                    quote_spanned!{Span::call_site() => #ident},
+                    quote!{ resolve_mutex_arc },
+                    quote_spanned!{Span::call_site() => #ident},
+                ),
+                ResolveType::OwnedReadGuard => (
+                    quote_spanned!{Span::call_site() => #ident},
+                    quote!{ resolve_owned_read_guard },
+                    quote_spanned!{Span::call_site() => #ident},
+                ),
+                ResolveType::OwnedWriteGuard => (
+                    quote_spanned!{Span::call_site() => #ident},
+                    quote!{ resolve_owned_write_guard },
+                    quote_spanned!{Span::call_site() => #ident},
+                ),
+            };
+            let (access, optional) = match arg_mutability {
+                ResolveType::ImmutableBorrow         => (quote!{ ImmutableRef }, false),
+                ResolveType::MutableBorrow           => (quote!{ MutableRef }, false),
+                ResolveType::OwnedValue              => (quote!{ OwnedValue }, false),
+                ResolveType::OptionalImmutableBorrow => (quote!{ ImmutableRef }, true),
+                ResolveType::OptionalMutableBorrow   => (quote!{ MutableRef }, true),
+                ResolveType::OptionalOwnedValue      => (quote!{ OwnedValue }, true),
+                ResolveType::SharedArc               => (quote!{ SharedArc }, false),
+                ResolveType::SharedRwLock            => (quote!{ SharedRwLock }, false),
+                ResolveType::SharedMutex             => (quote!{ SharedMutex }, false),
+                ResolveType::OwnedReadGuard          => (quote!{ ImmutableRef }, false),
+                ResolveType::OwnedWriteGuard         => (quote!{ MutableRef }, false),
+            };
+            let mut shared = matches!(arg_mutability,
+                ResolveType::SharedArc | ResolveType::SharedRwLock | ResolveType::SharedMutex |
+                ResolveType::OwnedReadGuard | ResolveType::OwnedWriteGuard);
+
+            // References which must outlive the resolver are resolved through owned guards instead
+            // (which deref just like the borrowing guards, so are passed in the same way)
+            let owned_method = match arg_mutability {
+                ResolveType::ImmutableBorrow         => Some(quote!{ resolve_owned_read_guard }),
+                ResolveType::MutableBorrow           => Some(quote!{ resolve_owned_write_guard }),
+                ResolveType::OptionalImmutableBorrow => Some(quote!{ resolve_optional_owned_read_guard }),
+                ResolveType::OptionalMutableBorrow   => Some(quote!{ resolve_optional_owned_write_guard }),
+                _ => None,
+            };
+            let method = match owned_method {
+                Some(owned_method) if owned => {
+                    shared = true;
+                    owned_method
+                },
+                _ => method,
+            };
+
+            let dependency = quote_spanned!{Span::call_site() => ::rustdi::Dependency::new::<#arg_type>(::rustdi::Access::#access, #optional)};
+            let resolver_trait = if shared { quote!{ SharedResolver } } else { quote!{ Resolver } };
+            let resolve_method = quote_spanned!{Span::call_site() => #method::<#arg_type>};
+            let resolve = quote_spanned!{Span::call_site() => ::rustdi::#resolver_trait::#resolve_method(resolver)};
+            ResolvedArg{binding, resolve, resolve_method, shared, pass, dependency}
+        })
+        .collect();
+    Ok(args)
+}
@@ -0,0 +1,602 @@
+use crate::proc_macro2::{Span, TokenStream, TokenTree, Group, Delimiter, Spacing};
+
+use syn::{ItemFn, FnArg, ArgCaptured, ArgSelf, Pat, PatIdent, ArgSelfRef, Lifetime, PathArguments, GenericArgument, ParenthesizedGenericArguments, Type, TypePath, TypeImplTrait, TypeReference, TypeBareFn, WherePredicate, PredicateType, TypeParam, ConstParam, GenericParam, ReturnType, Ident};
+use syn::parse::{Error, Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::visit_mut::{self, VisitMut};
+
+use args::{resolve_args, resolve_owned_args, resolver_bound};
+
+// Options given to the inject attribute: a concrete resolver type for the wrapper to take
+// (rather than being generic over any resolver), an error type which resolution errors
+// are converted into with From, e.g. #[inject(resolver = RequestResolver, error = AppError)],
+// and whether a function returning a Result should have it flattened into the wrapper's Result
+#[derive(Default)]
+pub struct InjectOptions {
+    resolver: Option<Type>,
+    error: Option<Type>,
+    flatten: bool,
+}
+impl Parse for InjectOptions {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let mut options = InjectOptions::default();
+        while !input.is_empty() {
+            let key : Ident = input.parse()?;
+            let given_twice = Error::new(key.span(), format!("the {} option is given more than once", key));
+            match key.to_string().as_str() {
+                "flatten" => {
+                    if options.flatten {
+                        return Err(given_twice);
+                    }
+                    options.flatten = true;
+                },
+                "resolver" | "error" => {
+                    let option = if key == "resolver" { &mut options.resolver } else { &mut options.error };
+                    if option.is_some() {
+                        return Err(given_twice);
+                    }
+                    input.parse::<Token![=]>()?;
+                    *option = Some(input.parse()?);
+                },
+                _ => return Err(Error::new(key.span(), "unknown inject option, expected `resolver`, `error` or `flatten`")),
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(options)
+    }
+}
+
+// If a type is Result<T, E> then return T and E
+fn result_types(ty: &Type) -> Option<(Type, Type)> {
+    let segment = match ty {
+        Type::Path(TypePath{ qself: None, path }) => path.segments.iter().last()?,
+        _ => return None,
+    };
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if segment.ident == "Result" && args.args.len() == 2 => {
+            match (&args.args[0], &args.args[1]) {
+                (GenericArgument::Type(ok), GenericArgument::Type(err)) => Some((ok.clone(), err.clone())),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+// Replaces impl Trait argument types with named type parameters, so that the wrapper
+// can resolve them and pass them on to the original function explicitly
+struct ImplTraitParams {
+    params: Vec<TypeParam>,
+}
+impl VisitMut for ImplTraitParams {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::ImplTrait(TypeImplTrait{ bounds, .. }) = ty {
+            let ident = Ident::new(format!("__RustdiImpl{}", self.params.len()).as_str(), Span::call_site());
+            self.params.push(parse_quote!{ #ident: #bounds });
+            *ty = parse_quote!{ #ident };
+        } else {
+            visit_mut::visit_type_mut(self, ty);
+        }
+    }
+}
+
+// Give the elided lifetimes of the borrows in a type a lifetime, recording whether it borrows
+// anything (lifetimes elided in fn pointer and Fn trait types are left alone, as they belong
+// to those types rather than to the argument)
+struct ElidedLifetimes {
+    lifetime: Lifetime,
+    borrows: bool,
+}
+impl VisitMut for ElidedLifetimes {
+    fn visit_type_reference_mut(&mut self, reference: &mut TypeReference) {
+        self.borrows = true;
+        reference.lifetime.get_or_insert_with(|| self.lifetime.clone());
+        visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        self.borrows = true;
+        if lifetime.ident == "_" {
+            *lifetime = self.lifetime.clone();
+        }
+    }
+
+    fn visit_type_bare_fn_mut(&mut self, _: &mut TypeBareFn) {}
+    fn visit_parenthesized_generic_arguments_mut(&mut self, _: &mut ParenthesizedGenericArguments) {}
+}
+
+// Track the depth of angle brackets (e.g. generics) in a token stream, ignoring the > of a -> arrow
+fn angle_depth(depth: &mut usize, previous: Option<&TokenTree>, token: &TokenTree) {
+    if let TokenTree::Punct(punct) = token {
+        match punct.as_char() {
+            '<' => *depth += 1,
+            '>' => match previous {
+                Some(TokenTree::Punct(p)) if p.as_char() == '-' && p.spacing() == Spacing::Joint => (),
+                _ => *depth = depth.saturating_sub(1),
+            },
+            _ => (),
+        }
+    }
+}
+
+// The positions of arguments marked #[arg], which are supplied by the caller of the wrapper rather
+// than injected, and of arguments marked #[from_resolver], which are produced by their type's
+// FromResolver implementation rather than resolved as a service
+#[derive(Default)]
+pub struct ArgAttributes {
+    explicit: Vec<usize>,
+    extracted: Vec<usize>,
+}
+
+// syn can't parse attributes on function arguments, so they are removed from the function's
+// tokens before it is parsed, returning the positions of the marked arguments
+pub fn strip_arg_attributes(input: TokenStream) -> (TokenStream, ArgAttributes) {
+    let mut tokens = input.into_iter().collect::<Vec<_>>();
+    let mut attributes = ArgAttributes::default();
+
+    // Find the function's argument list: the first parenthesised group after fn that isn't in its generics
+    let fn_index = match tokens.iter().position(|token| matches!(token, TokenTree::Ident(ident) if ident == "fn")) {
+        Some(index) => index,
+        None => return (tokens.into_iter().collect(), attributes),
+    };
+    let mut depth = 0;
+    let mut args_index = None;
+    for index in fn_index..tokens.len() {
+        match &tokens[index] {
+            TokenTree::Group(group) if depth == 0 && group.delimiter() == Delimiter::Parenthesis => {
+                args_index = Some(index);
+                break;
+            },
+            token => angle_depth(&mut depth, if index > 0 { tokens.get(index - 1) } else { None }, token),
+        }
+    }
+    let args_index = match args_index {
+        Some(index) => index,
+        None => return (tokens.into_iter().collect(), attributes),
+    };
+
+    // Remove #[arg] and #[from_resolver] from the start of each argument, remembering which argument they were on
+    let args = match &tokens[args_index] {
+        TokenTree::Group(group) => group.clone(),
+        _ => unreachable!(),
+    };
+    let arg_tokens = args.stream().into_iter().collect::<Vec<_>>();
+    let mut stripped = Vec::new();
+    let mut arg_index = 0;
+    let mut arg_start = true;
+    let mut depth = 0;
+    let mut index = 0;
+    while index < arg_tokens.len() {
+        let token = &arg_tokens[index];
+        if arg_start {
+            if let (TokenTree::Punct(pound), Some(TokenTree::Group(attr))) = (token, arg_tokens.get(index + 1)) {
+                let attr_tokens = attr.stream().into_iter().collect::<Vec<_>>();
+                let marked = match attr_tokens.as_slice() {
+                    _ if pound.as_char() != '#' || attr.delimiter() != Delimiter::Bracket => None,
+                    [TokenTree::Ident(ident)] if ident == "arg"           => Some(&mut attributes.explicit),
+                    [TokenTree::Ident(ident)] if ident == "from_resolver" => Some(&mut attributes.extracted),
+                    _ => None,
+                };
+                if let Some(marked) = marked {
+                    marked.push(arg_index);
+                    index += 2;
+                    continue;
+                }
+            }
+        }
+        arg_start = false;
+        match token {
+            TokenTree::Punct(punct) if depth == 0 && punct.as_char() == ',' => {
+                arg_index += 1;
+                arg_start = true;
+            },
+            token => angle_depth(&mut depth, if index > 0 { arg_tokens.get(index - 1) } else { None }, token),
+        }
+        stripped.push(token.clone());
+        index += 1;
+    }
+    let mut args_group = Group::new(Delimiter::Parenthesis, stripped.into_iter().collect());
+    args_group.set_span(args.span());
+    tokens[args_index] = TokenTree::Group(args_group);
+
+    (tokens.into_iter().collect(), attributes)
+}
+
+// Implement Handler for the type named after a non-generic function, listing the function's
+// dependencies (and implement Inject for it by calling the wrapper, unless it is async, has
+// arguments that must be passed explicitly or converts its errors). The impls go alongside
+// the wrapper, so that argument types are resolved where they were written.
+// Arguments produced by FromResolver aren't listed, as what they depend on is up to their type.
+fn handler_impls(func: &ItemFn, attributes: &ArgAttributes, options: &InjectOptions) -> Result<TokenStream, Error> {
+    let ArgAttributes{ explicit, extracted } = attributes;
+    let ident = &func.ident;
+    let name = ident.to_string();
+    let decl = (*func.decl).clone();
+
+    let (extracted_inputs, injected_inputs) : (Vec<_>, Vec<_>) = decl.inputs.into_iter()
+        .enumerate()
+        .filter(|(index, _)| !explicit.contains(index))
+        .partition(|(index, _)| extracted.contains(index));
+    let injected_inputs = injected_inputs.into_iter().map(|(_, arg)| arg);
+    let args = if func.asyncness.is_some() { resolve_owned_args(injected_inputs)? } else { resolve_args(injected_inputs)? };
+    let dependencies = args.iter().map(|arg| &arg.dependency);
+    let extracted_types = extracted_inputs.into_iter()
+        .filter_map(|(_, arg)| match arg {
+            FnArg::Captured(ArgCaptured{ ty, .. }) => Some(ty),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let inject_impl = if func.asyncness.is_none() && explicit.is_empty() && options.error.is_none() && !options.flatten {
+        let return_type = match decl.output {
+            ReturnType::Default => quote!{ () },
+            ReturnType::Type(_, ty) => quote!{ #ty },
+        };
+        let (impl_generics, resolver_type) = match &options.resolver {
+            Some(resolver) => (None, quote!{ #resolver }),
+            None => {
+                let bound = resolver_bound(&args);
+                (Some(quote!{ <R: #bound> }), quote!{ R })
+            },
+        };
+        let where_clause = if extracted_types.is_empty() {
+            None
+        } else {
+            let bounds = extracted_types.iter().map(|ty| quote!{ #ty: ::rustdi::FromResolver<#resolver_type> });
+            Some(quote!{ where #(#bounds),* })
+        };
+        Some(quote!{
+            impl #impl_generics ::rustdi::Inject<#return_type, #resolver_type> for #ident #where_clause {
+                type Return = #return_type;
+
+                fn inject(&self, resolver: &#resolver_type) -> Result<#return_type, <#resolver_type as ::rustdi::Resolver>::Error> {
+                    #ident(resolver)
+                }
+            }
+        })
+    } else {
+        None
+    };
+
+    Ok(quote!{
+        impl ::rustdi::Handler for #ident {
+            fn name(&self) -> &'static str {
+                #name
+            }
+
+            fn dependencies(&self) -> Vec<::rustdi::Dependency> {
+                vec![#(#dependencies),*]
+            }
+        }
+
+        #inject_impl
+    })
+}
+
+pub fn expand(mut func: ItemFn, attributes: ArgAttributes, options: InjectOptions) -> Result<TokenStream, Error> {
+    let ArgAttributes{ explicit, extracted } = &attributes;
+
+    // Give impl Trait arguments names, so that the wrapper can be generic over them
+    let mut impl_trait_params = ImplTraitParams{ params: Vec::new() };
+    for arg in func.decl.inputs.iter_mut() {
+        if let FnArg::Captured(ArgCaptured{ ty, .. }) = arg {
+            impl_trait_params.visit_type_mut(ty);
+        }
+    }
+    func.decl.generics.params.extend(impl_trait_params.params.into_iter().map(GenericParam::Type));
+
+    // Generate parts of the output function
+    let ident = func.ident.clone();
+    let visibility = func.vis.clone();
+    let mut return_type = match func.decl.output.clone() {
+        ReturnType::Default => quote!{ () },
+        ReturnType::Type(_, ty) => quote!{ #ty },
+    };
+    //let container_type = quote_spanned!{Span::call_site() => &::rustdi::ServiceContainer};
+    let resolver_trait = quote_spanned!{Span::call_site() => ::rustdi::Resolver};
+    let future_trait = quote_spanned!{Span::call_site() => ::std::future::Future};
+    let (resolver_type, resolver_error) = match &options.resolver {
+        Some(resolver) => (quote!{ #resolver }, quote!{ <#resolver as #resolver_trait>::Error }),
+        None           => (quote!{ R }, quote!{ R::Error }),
+    };
+    let mut error_type = match &options.error {
+        Some(error) => quote!{ #error },
+        None        => resolver_error.clone(),
+    };
+
+    // Flattened functions' results are unwrapped with ? so that both their errors and resolution
+    // errors are converted into the wrapper's error type (which is the function's error type
+    // unless another is given). Results are named through HandlerResult if they are aliased.
+    let unwrap_result = if options.flatten { Some(quote!{ ? }) } else { None };
+    if options.flatten {
+        let ty = match &func.decl.output {
+            ReturnType::Type(_, ty) => ty,
+            ReturnType::Default => return Err(Error::new(ident.span(), "flattened functions must return a Result")),
+        };
+        let (ok, err) = match result_types(ty) {
+            Some((ok, err)) => (quote!{ #ok }, quote!{ #err }),
+            None => (
+                quote!{ <#ty as ::rustdi::HandlerResult>::Ok },
+                quote!{ <#ty as ::rustdi::HandlerResult>::Error },
+            ),
+        };
+        return_type = ok;
+        if options.error.is_none() {
+            error_type = err;
+        }
+    }
+    let original_func_ident = Ident::new(format!("{}_orig", ident).as_str(), ident.span());
+    let mut original_func = func.clone();
+    original_func.ident = original_func_ident.clone();
+
+    // Methods pass their self argument through rather than injecting it
+    let (receiver, inputs) : (Vec<FnArg>, Vec<FnArg>) = func.decl.inputs.clone().into_iter()
+        .partition(|arg| matches!(arg, FnArg::SelfRef(_) | FnArg::SelfValue(_)));
+    let receiver = receiver.into_iter().next();
+    if let Some(receiver) = &receiver {
+        if explicit.contains(&0) {
+            return Err(Error::new(receiver.span(), "self is always passed to the wrapper, so it can't be marked #[arg]"));
+        }
+        if extracted.contains(&0) {
+            return Err(Error::new(receiver.span(), "self is always passed to the wrapper, so it can't be marked #[from_resolver]"));
+        }
+    }
+
+    // Arguments marked #[arg] become arguments of the wrapper (named after the original
+    // argument where it is a plain identifier), arguments marked #[from_resolver] are
+    // produced by their types, and the rest are injected
+    let offset = if receiver.is_some() { 1 } else { 0 };
+    let mut explicit_args = Vec::new();
+    let mut extracted_types = Vec::new();
+    let mut injected_inputs = Vec::new();
+    for (index, arg) in inputs.into_iter().enumerate() {
+        if extracted.contains(&(index + offset)) {
+            match arg {
+                FnArg::Captured(ArgCaptured{ ty, .. }) => extracted_types.push(ty),
+                arg => return Err(Error::new(arg.span(), "arguments marked #[from_resolver] must have a type, e.g. `#[from_resolver] agent: UserAgent`")),
+            }
+            continue;
+        }
+        if !explicit.contains(&(index + offset)) {
+            injected_inputs.push(arg);
+            continue;
+        }
+        let explicit_arg = match arg {
+            FnArg::Captured(ArgCaptured{ pat: Pat::Ident(PatIdent{ ident, subpat: None, .. }), ty, .. }) => (ident, ty),
+            FnArg::Captured(ArgCaptured{ ty, .. }) => (Ident::new(format!("__rustdi_explicit{}", index).as_str(), Span::call_site()), ty),
+            arg => return Err(Error::new(arg.span(), "arguments marked #[arg] must have a type, e.g. `#[arg] user_id: u32`")),
+        };
+        explicit_args.push(explicit_arg);
+    }
+
+    // Async wrappers return futures which capture the borrowed arguments passed to them (self and
+    // any borrows marked #[arg]), so such futures are bounded by a lifetime which the borrows are
+    // given where their lifetimes are elided, and which any other lifetimes must outlive
+    let future_lifetime = Lifetime::new("'__rustdi_future", Span::call_site());
+    let mut elided_lifetimes = ElidedLifetimes{ lifetime: future_lifetime.clone(), borrows: false };
+    if func.asyncness.is_some() {
+        for (_, ty) in explicit_args.iter_mut() {
+            elided_lifetimes.visit_type_mut(ty);
+        }
+    }
+    let captures_borrows = func.asyncness.is_some()
+        && (receiver.is_some() || elided_lifetimes.borrows || func.decl.generics.lifetimes().next().is_some());
+    let wrapper_args = explicit_args.iter().map(|(ident, ty)| quote!{ #ident: #ty }).collect::<Vec<_>>();
+
+    // Generate code to resolve injected arguments from container with requested mutability,
+    // and to produce extracted arguments from the resolver
+    // (async functions' arguments are resolved as owned guards, so that their futures don't
+    // borrow from the resolver and can be 'static and Send if the services allow it)
+    let args = if func.asyncness.is_some() { resolve_owned_args(injected_inputs)? } else { resolve_args(injected_inputs)? };
+    let extracted_idents = (0..extracted_types.len())
+        .map(|index| Ident::new(format!("__rustdi_extracted{}", index).as_str(), Span::call_site()))
+        .collect::<Vec<_>>();
+    let bindings = args.iter().map(|arg| arg.binding.clone())
+        .chain(extracted_idents.iter().map(|ident| quote!{ #ident }))
+        .collect::<Vec<_>>();
+    let resolves = args.iter().map(|arg| arg.resolve.clone())
+        .chain(extracted_types.iter().map(|ty| quote!{ <#ty as ::rustdi::FromResolver<#resolver_type>>::from_resolver(resolver) }))
+        .collect::<Vec<_>>();
+
+    // Explicit, extracted and injected arguments are passed to the original function in their original order
+    let mut explicit_passes = explicit_args.iter().map(|(ident, _)| quote!{ #ident });
+    let mut extracted_passes = extracted_idents.iter().map(|ident| quote!{ #ident });
+    let mut injected_passes = args.iter().map(|arg| arg.pass.clone());
+    let passes = (0..explicit_args.len() + extracted_idents.len() + args.len())
+        .map(|index| if explicit.contains(&(index + offset)) {
+            explicit_passes.next().unwrap()
+        } else if extracted.contains(&(index + offset)) {
+            extracted_passes.next().unwrap()
+        } else {
+            injected_passes.next().unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    // Type parameters are passed to the original function explicitly, as they can't
+    // necessarily be inferred from its arguments
+    let type_params = func.decl.generics.params.iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(TypeParam{ ident, .. }) => Some(ident),
+            GenericParam::Const(ConstParam{ ident, .. }) => Some(ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let turbofish = if type_params.is_empty() { None } else { Some(quote!{ ::<#(#type_params),*> }) };
+    let call = match receiver {
+        None    => quote!{ #original_func_ident #turbofish (#(#passes),*) },
+        Some(_) => quote!{ self.#original_func_ident #turbofish (#(#passes),*) },
+    };
+
+    // The wrapper keeps the original function's generics, and adds the resolver's (unless it is given)
+    // and the lifetime of any borrows its future captures. Type parameters must be 'static in order
+    // to be resolved.
+    let mut generics = func.decl.generics.clone();
+    let outliving_lifetimes = generics.lifetimes().map(|param| param.lifetime.clone()).collect::<Vec<_>>();
+    if captures_borrows {
+        generics.params.insert(0, parse_quote!{ #future_lifetime });
+    }
+    // (the bound goes wherever the parameter's other bounds are, to keep clippy happy)
+    let mut where_bounded = Vec::new();
+    if let Some(where_clause) = generics.where_clause.as_mut() {
+        for predicate in where_clause.predicates.iter_mut() {
+            if let WherePredicate::Type(PredicateType{ bounded_ty: Type::Path(TypePath{ qself: None, path }), bounds, .. }) = predicate {
+                if path.segments.len() == 1 && !where_bounded.contains(&path.segments[0].ident) {
+                    where_bounded.push(path.segments[0].ident.clone());
+                    bounds.push(parse_quote!{ 'static });
+                }
+            }
+        }
+    }
+    for param in generics.type_params_mut() {
+        if !where_bounded.contains(&param.ident) {
+            param.colon_token.get_or_insert_with(Default::default);
+            param.bounds.push(parse_quote!{ 'static });
+        }
+    }
+    if options.resolver.is_none() {
+        let bound = resolver_bound(&args);
+        generics.params.push(parse_quote!{ R: #bound });
+        if options.error.is_some() || options.flatten {
+            generics.make_where_clause().predicates.push(parse_quote!{ #error_type: ::std::convert::From<R::Error> });
+        }
+    }
+    for ty in &extracted_types {
+        generics.make_where_clause().predicates.push(parse_quote!{ #ty: ::rustdi::FromResolver<#resolver_type> });
+    }
+    if captures_borrows {
+        let predicates = &mut generics.make_where_clause().predicates;
+        predicates.push(parse_quote!{ #resolver_type: #future_lifetime });
+        for lifetime in &outliving_lifetimes {
+            predicates.push(parse_quote!{ #lifetime: #future_lifetime });
+        }
+        if receiver.is_some() {
+            predicates.push(parse_quote!{ Self: #future_lifetime });
+        }
+        if let Some(FnArg::SelfRef(ArgSelfRef{ lifetime: Some(lifetime), .. })) = &receiver {
+            predicates.push(parse_quote!{ #lifetime: #future_lifetime });
+        }
+    }
+    let where_clause = generics.where_clause.clone();
+
+    // Async functions resolve their arguments up front and then return a future which
+    // owns the resolved guards, and which resolves to the original function's return value.
+    // (resolution errors are converted to the error type given in the options by ?)
+    let future_bound = if captures_borrows { Some(quote!{ + #future_lifetime }) } else { None };
+    let (output, body) = match func.asyncness {
+        None => (
+            quote!{ Result<#return_type, #error_type> },
+            quote!{
+                #(let #bindings = #resolves?;)*
+                let ret = #call #unwrap_result;
+                Ok(ret)
+            },
+        ),
+        Some(asyncness) => (
+            quote!{ impl #future_trait<Output = Result<#return_type, #error_type>> #future_bound },
+            // (async blocks are spanned to the async keyword so that they are parsed
+            // using the edition of the crate the function is in)
+            quote_spanned!{asyncness.span =>
+                let resolved = (move || -> Result<_, #resolver_error> { Ok((#(#resolves?,)*)) })();
+                async move {
+                    let (#(#bindings,)*) = resolved?;
+                    let ret = #call.await #unwrap_result;
+                    Ok(ret)
+                }
+            },
+        ),
+    };
+    let resolver_arg = quote!{ resolver: &#resolver_type };
+
+    // Write out new wrapped function
+    let expanded = match receiver {
+
+        // The original function is kept alongside the wrapper, and a type of the same name is
+        // declared with an associated call function which forwards to it (so that it can be
+        // called as name::call with plain arguments) and, for non-generic functions, a Handler
+        // constant. A type is used rather than a module so that paths in the function's
+        // signature mean the same thing wherever it is declared (including in a function body).
+        None => {
+            let call_idents = (0..func.decl.inputs.len())
+                .map(|index| Ident::new(format!("__rustdi_arg{}", index).as_str(), Span::call_site()))
+                .collect::<Vec<_>>();
+            let call_args = func.decl.inputs.iter().zip(&call_idents)
+                .map(|(arg, ident)| match arg {
+                    FnArg::Captured(ArgCaptured{ ty, .. }) => Ok(quote!{ #ident: #ty }),
+                    arg => Err(Error::new(arg.span(), "the inject macro only supports arguments with a type")),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let call_generics = &func.decl.generics;
+            let call_where_clause = &func.decl.generics.where_clause;
+            let call_output = &func.decl.output;
+            let unsafety = &func.unsafety;
+            let call_fn = match func.asyncness {
+                None => quote!{
+                    #visibility #unsafety fn call #call_generics (#(#call_args),*) #call_output #call_where_clause {
+                        #original_func_ident #turbofish (#(#call_idents),*)
+                    }
+                },
+                Some(asyncness) => quote_spanned!{asyncness.span =>
+                    #visibility #unsafety async fn call #call_generics (#(#call_args),*) #call_output #call_where_clause {
+                        #original_func_ident #turbofish (#(#call_idents),*).await
+                    }
+                },
+            };
+            let (handler, handler_impls) = if func.decl.generics.params.is_empty() {
+                let handler = quote!{
+                    #[allow(non_upper_case_globals)]
+                    #visibility const Handler: #ident = #ident {};
+                };
+                (Some(handler), Some(handler_impls(&func, &attributes, &options)?))
+            } else {
+                (None, None)
+            };
+            quote!{
+
+                #[doc(hidden)]
+                #original_func
+
+                #visibility fn #ident #generics (#resolver_arg #(, #wrapper_args)*) -> #output #where_clause {
+                    #body
+                }
+
+                #[allow(non_camel_case_types, dead_code)]
+                #visibility struct #ident {}
+
+                #[allow(dead_code)]
+                impl #ident {
+                    #call_fn
+                    #handler
+                }
+
+                #handler_impls
+
+            }
+        },
+
+        // Types can't be declared in impl blocks, so the original method is kept alongside
+        // the wrapper as {name}_orig (with a `mut self` receiver dropping its mut, and an
+        // async method's self reference living as long as the future)
+        Some(receiver) => {
+            let receiver = match receiver {
+                FnArg::SelfValue(ArgSelf{ self_token, .. }) => FnArg::SelfValue(ArgSelf{ mutability: None, self_token }),
+                FnArg::SelfRef(ArgSelfRef{ and_token, lifetime: None, mutability, self_token }) if captures_borrows => {
+                    FnArg::SelfRef(ArgSelfRef{ and_token, lifetime: Some(future_lifetime), mutability, self_token })
+                },
+                receiver => receiver,
+            };
+
+            quote!{
+
+                #original_func
+
+                #visibility fn #ident #generics (#receiver, #resolver_arg #(, #wrapper_args)*) -> #output #where_clause {
+                    #body
+                }
+
+            }
+        },
+    };
+    Ok(expanded)
+}
@@ -0,0 +1,121 @@
+use crate::proc_macro2::{Span, TokenStream};
+
+use syn::{DeriveInput, ImplItemMethod, Data, DataStruct, Fields, Field, ReturnType, Ident, Meta, NestedMeta, Lit};
+
+use syn::parse::Error;
+use syn::spanned::Spanned;
+
+use args::{resolve_args, resolver_bound, service_type_and_resolve_type, ResolveType};
+
+// The name given by a #[named("...")] attribute on a field, if any
+fn field_binding_name(field: &Field) -> Result<Option<String>, Error> {
+    let attr = match field.attrs.iter().find(|attr| attr.path.segments.len() == 1 && attr.path.segments[0].ident == "named") {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    match attr.interpret_meta() {
+        Some(Meta::List(ref list)) if list.nested.len() == 1 => match list.nested.iter().next() {
+            Some(NestedMeta::Literal(Lit::Str(name))) => Ok(Some(name.value())),
+            _ => Err(Error::new(attr.span(), "the named attribute takes a single string, e.g. #[named(\"primary\")]")),
+        },
+        _ => Err(Error::new(attr.span(), "the named attribute takes a single string, e.g. #[named(\"primary\")]")),
+    }
+}
+
+// Generate code to resolve a field's value from the container. Fields are classified like
+// #[inject] arguments, but as the struct outlives the container they must own their values.
+fn resolve_field(field: &Field) -> Result<TokenStream, Error> {
+    let (ty, resolve_type, _) = service_type_and_resolve_type(field.ty.clone())?;
+    let resolve = match (resolve_type, field_binding_name(field)?) {
+        (ResolveType::SharedArc, None)          => quote_spanned!{Span::call_site() => container.resolve_arc::<#ty>()?},
+        (ResolveType::SharedArc, Some(name))    => quote_spanned!{Span::call_site() => container.resolve_named_arc::<#ty>(#name)?},
+        (ResolveType::SharedRwLock, None)       => quote_spanned!{Span::call_site() => container.resolve_rwlock_arc::<#ty>()?},
+        (ResolveType::SharedRwLock, Some(name)) => quote_spanned!{Span::call_site() => container.resolve_named_rwlock_arc::<#ty>(#name)?},
+        (ResolveType::SharedMutex, None)        => quote_spanned!{Span::call_site() => container.resolve_mutex_arc::<#ty>()?},
+        (ResolveType::SharedMutex, Some(name))  => quote_spanned!{Span::call_site() => container.resolve_named_mutex_arc::<#ty>(#name)?},
+        (ResolveType::OwnedValue, None)         => quote_spanned!{Span::call_site() => container.resolve_owned_value::<#ty>()?},
+        (ResolveType::OwnedValue, Some(name))   => quote_spanned!{Span::call_site() => container.resolve_named_owned_value::<#ty>(#name)?},
+        (ResolveType::OptionalOwnedValue, None) => quote_spanned!{Span::call_site() => container.resolve_optional_owned_value::<#ty>()?},
+        (ResolveType::OptionalOwnedValue, Some(_)) => {
+            return Err(Error::new(field.ty.span(), "named Option fields can't be injected"));
+        },
+        (ResolveType::OwnedReadGuard, _) | (ResolveType::OwnedWriteGuard, _) => {
+            return Err(Error::new(field.ty.span(), "owned guard fields can't be injected, use an Arc<RwLock<_>> instead"));
+        },
+        _ => return Err(Error::new(field.ty.span(), "reference fields can't be injected, use an Arc instead")),
+    };
+    Ok(resolve)
+}
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream, Error> {
+    let ident = input.ident.clone();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Generate an expression constructing the struct with each field resolved from the container
+    let construct = match input.data {
+        Data::Struct(DataStruct{ fields: Fields::Named(fields), .. }) => {
+            let field_values = fields.named.iter()
+                .map(|field| {
+                    let field_ident = field.ident.clone();
+                    let value = resolve_field(field)?;
+                    Ok(quote!{ #field_ident: #value })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            quote!{ #ident { #(#field_values),* } }
+        },
+        Data::Struct(DataStruct{ fields: Fields::Unnamed(fields), .. }) => {
+            let field_values = fields.unnamed.iter().map(resolve_field).collect::<Result<Vec<_>, Error>>()?;
+            quote!{ #ident ( #(#field_values),* ) }
+        },
+        Data::Struct(DataStruct{ fields: Fields::Unit, .. }) => quote!{ #ident },
+        _ => return Err(Error::new(ident.span(), "Injectable can only be derived for structs")),
+    };
+
+    let container_type = quote_spanned!{Span::call_site() => ::rustdi::ServiceContainer};
+    let error_type = quote_spanned!{Span::call_site() => ::rustdi::ResolveError};
+    let resolver_trait = quote_spanned!{Span::call_site() => ::rustdi::Resolver};
+    let shared_resolver_trait = quote_spanned!{Span::call_site() => ::rustdi::SharedResolver};
+
+    Ok(quote!{
+
+        impl #impl_generics ::rustdi::Injectable for #ident #ty_generics #where_clause {
+            fn inject_new(container: &#container_type) -> Result<Self, #error_type> {
+                #[allow(unused_imports)]
+                use #resolver_trait;
+                #[allow(unused_imports)]
+                use #shared_resolver_trait;
+                Ok(#construct)
+            }
+        }
+
+    })
+}
+
+pub fn expand_constructor(constructor: ImplItemMethod) -> Result<TokenStream, Error> {
+
+    // Generate parts of the injectable constructor
+    let ident = constructor.sig.ident.clone();
+    let visibility = constructor.vis.clone();
+    let return_type = match constructor.sig.decl.output.clone() {
+        ReturnType::Default => return Err(Error::new(ident.span(), "#[injectable] constructors must return Self")),
+        ReturnType::Type(_, ty) => ty,
+    };
+    let injectable_ident = Ident::new(format!("{}_injected", ident).as_str(), ident.span());
+    let args = resolve_args(constructor.sig.decl.inputs.clone())?;
+    let resolver_trait = resolver_bound(&args);
+    let bindings = args.iter().map(|arg| &arg.binding);
+    let resolves = args.iter().map(|arg| &arg.resolve);
+    let passes = args.iter().map(|arg| &arg.pass);
+
+    // Write out the original constructor along with the injectable one
+    Ok(quote!{
+
+        #constructor
+
+        #visibility fn #injectable_ident<R: #resolver_trait>(resolver: &R) -> Result<#return_type, R::Error> {
+            #(let #bindings = #resolves?;)*
+            Ok(Self::#ident(#(#passes),*))
+        }
+
+    })
+}
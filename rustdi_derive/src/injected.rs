@@ -0,0 +1,51 @@
+use crate::proc_macro2::{TokenStream};
+
+use syn::{ExprClosure, FnArg, ArgCaptured};
+use syn::parse::Error;
+use syn::spanned::Spanned;
+
+use args::{resolve_args, resolver_bound};
+
+pub fn expand(closure: ExprClosure) -> Result<TokenStream, Error> {
+    if let Some(asyncness) = closure.asyncness {
+        return Err(Error::new(asyncness.span, "async closures can't be injected"));
+    }
+
+    // Generate code to resolve the closure's arguments from a resolver with requested mutability
+    let arg_types = closure.inputs.iter()
+        .map(|arg| match arg {
+            FnArg::Captured(ArgCaptured{ ty, .. }) => Ok(ty.clone()),
+            arg => Err(Error::new(arg.span(), "injected closure arguments must have a type, e.g. `|state: &AppState|`")),
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let args = resolve_args(closure.inputs.clone())?;
+    let resolver_trait = resolver_bound(&args);
+    let bindings = args.iter().map(|arg| &arg.binding);
+    let resolves = args.iter().map(|arg| &arg.resolve);
+    let passes = args.iter().map(|arg| &arg.pass);
+    let fn_bound = quote!{ Fn(#(#arg_types),*) -> Ret };
+
+    // Closures can't be generic over the resolver, so the closure is wrapped in a type which
+    // implements Inject for any resolver. It is passed through a function with the closure's
+    // signature as a bound so that the closure's reference arguments accept any lifetime.
+    Ok(quote!{
+        {
+            struct Injected<F>(F);
+
+            impl<F, Ret, R: #resolver_trait> ::rustdi::Inject<Ret, R> for Injected<F> where F: #fn_bound {
+                type Return = Ret;
+
+                fn inject(&self, resolver: &R) -> Result<Ret, R::Error> {
+                    #(let #bindings = #resolves?;)*
+                    Ok((self.0)(#(#passes),*))
+                }
+            }
+
+            fn injected<F, Ret>(closure: F) -> Injected<F> where F: #fn_bound {
+                Injected(closure)
+            }
+
+            injected(#closure)
+        }
+    })
+}
@@ -1,81 +1,82 @@
 extern crate proc_macro;
 extern crate proc_macro2;
-extern crate syn;
+#[macro_use] extern crate syn;
 #[macro_use] extern crate quote;
 
 use crate::proc_macro::{TokenStream};
-use crate::proc_macro2::{Span};
 
-use syn::{ItemFn, FnArg, ArgCaptured, Type, ReturnType, TypePath, TypeReference, Ident};
-use quote::ToTokens;
+use syn::{DeriveInput, Expr, Item, ImplItemMethod};
+use syn::parse::Error;
+use syn::spanned::Spanned;
+
+mod args;
+mod inject;
+mod injectable;
+mod injected;
+mod resolve;
+
+// Wraps a function so that its arguments are resolved from a resolver, e.g. a #[inject]
+// fn handler(config: &AppConfig, state: &mut AppState) is called as handler(&container).
+// An async fn's wrapper resolves its arguments up front and returns a future which doesn't
+// borrow from the resolver, so references are resolved as owned guards. Like std's lock
+// guards, owned guards of locked singletons can't be sent to other threads, so such futures
+// are 'static but not Send: take Arc<RwLock<T>> arguments instead for futures which are Send.
+#[proc_macro_attribute]
+pub fn inject(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let options = match syn::parse::<inject::InjectOptions>(attr) {
+        Ok(options) => options,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // Parse input as a function, once arguments marked #[arg] or #[from_resolver] have been noted
+    let (input, attributes) = inject::strip_arg_attributes(input.into());
+    let expanded = match syn::parse2(input) {
+        Ok(Item::Fn(func)) => inject::expand(func, attributes, options),
+        Ok(item) => Err(Error::new(item.span(), "#[inject] can only be used on functions")),
+        Err(err) => Err(err),
+    };
+    expanded.unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+// Wraps a closure whose arguments are all injected in a value implementing Inject, e.g.
+// injected!(|config: &AppConfig, state: &mut AppState| { ... }). (It can't be called inject!
+// as that would clash with the inject attribute.)
+#[proc_macro]
+pub fn injected(input: TokenStream) -> TokenStream {
+    let expanded = match syn::parse::<Expr>(input) {
+        Ok(Expr::Closure(closure)) => injected::expand(closure),
+        Ok(expr) => Err(Error::new(expr.span(), "injected! takes a closure, e.g. injected!(|state: &AppState| ...)")),
+        Err(err) => Err(err),
+    };
+    expanded.unwrap_or_else(|err| err.to_compile_error()).into()
+}
 
-enum ResolveType {
-    ImmutableBorrow,
-    MutableBorrow,
-    OwnedValue,
+// Resolves services from a resolver and binds them for a block, e.g.
+// resolve!(container => cfg: &AppConfig, state: &mut AppState; { ... }). A service which
+// can't be resolved is returned from the enclosing function as an error with ?.
+#[proc_macro]
+pub fn resolve(input: TokenStream) -> TokenStream {
+    let expanded = syn::parse::<resolve::ResolveInput>(input).and_then(resolve::expand);
+    expanded.unwrap_or_else(|err| err.to_compile_error()).into()
 }
 
+// Generates a {name}_injected constructor alongside an associated constructor function,
+// which resolves the constructor's arguments from a resolver. For example, an injectable
+// fn new(db: &Db) -> Self can be bound with container.bind_try_factory(Self::new_injected).
+// (An attribute on a method can only add methods, so it can't implement Injectable: derive
+// Injectable instead to use bind_injectable)
 #[proc_macro_attribute]
-pub fn inject(_attr: TokenStream, input: TokenStream) -> TokenStream {
-
-    // Parse input as a function (or panic)
-    let func : ItemFn = syn::parse(input.clone()).expect("The inject macro is only supported on functions");
-
-    // Extract argument info from function
-    let arg_types_and_mutabilities = func.clone().decl.inputs.into_iter()
-        .map(|arg| {
-            match arg {
-                FnArg::Captured(ArgCaptured{ ty: Type::Reference(TypeReference{ mutability, elem, .. }), .. }) => {
-                    if let Type::Path(TypePath{ qself: None, path: arg_path }) = *elem {
-
-                        let arg_mutability = match &mutability {
-                            Some(_) => ResolveType::MutableBorrow,
-                            None    => ResolveType::ImmutableBorrow
-                        };
-                        (arg_path, arg_mutability)
-
-                    } else { panic!("The inject macro only supports simple type arguments"); }
-                },
-                FnArg::Captured(ArgCaptured{ ty: Type::Path(TypePath{ qself: None, path: arg_path }), .. }) => {
-                    (arg_path, ResolveType::OwnedValue)
-                },
-                _ => panic!("The inject macro only supports simple type arguments"),
-            }
-        });
+pub fn injectable(_attr: TokenStream, input: TokenStream) -> TokenStream {
 
-    
-    // Generate parts of the output function
-    let ident = func.ident.clone();
-    let visibility = func.vis.clone();
-    let return_type = match func.decl.output.clone() {
-        ReturnType::Default => Box::new(quote!{()}) as Box<ToTokens>,
-        ReturnType::Type(_, ty) => ty as Box<ToTokens>,
-    };
-    //let container_type = quote_spanned!{Span::call_site() => &::rustdi::ServiceContainer};
-    let resolver_trait = quote_spanned!{Span::call_site() => ::rustdi::Resolver};
-    let original_func_ident = Ident::new(format!("{}_orig", ident).as_str(), ident.span());;
-    let mut original_func = func.clone();
-    original_func.ident = original_func_ident.clone();
-
-    // Generate code to resolve injected arguments from container with requested mutability
-    let args = arg_types_and_mutabilities.map(|(arg_path, arg_mutability)| {
-        match arg_mutability {
-            ResolveType::ImmutableBorrow => quote_spanned!{Span::call_site() => &*resolver.resolve_immutable_ref::<#arg_path>()?},
-            ResolveType::MutableBorrow   => quote_spanned!{Span::call_site() => &mut*resolver.resolve_mutable_ref::<#arg_path>()?},
-            ResolveType::OwnedValue      => quote_spanned!{Span::call_site() => resolver.resolve_owned_value::<#arg_path>()?},
-        }
-    });
-
-    // Write out new wrapped function
-    return quote!{
-        
-        #visibility fn #ident<R: #resolver_trait>(resolver: &R) -> Result<#return_type, R::Error> {
-            #original_func
-            let ret = #original_func_ident(#(#args),*);
-            return Ok(ret);
-        }
-
-    }.into();
+    // Parse input as an associated function
+    let expanded = syn::parse::<ImplItemMethod>(input).and_then(injectable::expand_constructor);
+    expanded.unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+#[proc_macro_derive(Injectable, attributes(named))]
+pub fn derive_injectable(input: TokenStream) -> TokenStream {
+    let expanded = syn::parse::<DeriveInput>(input).and_then(injectable::derive);
+    expanded.unwrap_or_else(|err| err.to_compile_error()).into()
 }
 
 // #[proc_macro_attribute]
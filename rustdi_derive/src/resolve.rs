@@ -0,0 +1,70 @@
+use crate::proc_macro2::{Span, TokenStream, Group, Delimiter};
+
+use syn::{Expr, FnArg, ArgCaptured, Block};
+use syn::parse::{Parse, ParseStream, Error};
+
+use args::resolve_args;
+
+// The input to resolve!: a resolver expression, the services to resolve (written like
+// function arguments) and the block to bind them for, e.g.
+// resolve!(container => cfg: &AppConfig, state: &mut AppState; { ... })
+pub struct ResolveInput {
+    resolver: Expr,
+    args: Vec<FnArg>,
+    block: Block,
+}
+
+impl Parse for ResolveInput {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let resolver = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let mut args = Vec::new();
+        while !input.peek(Token![;]) {
+            args.push(input.parse()?);
+            if input.peek(Token![;]) {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        input.parse::<Token![;]>()?;
+        let block = input.parse()?;
+        Ok(ResolveInput{resolver, args, block})
+    }
+}
+
+pub fn expand(input: ResolveInput) -> Result<TokenStream, Error> {
+    let ResolveInput{ resolver, args, block } = input;
+    let resolver_trait = quote_spanned!{Span::call_site() => ::rustdi::Resolver};
+    let shared_resolver_trait = quote_spanned!{Span::call_site() => ::rustdi::SharedResolver};
+
+    // Every service is resolved before any is bound, so that a failure to resolve one of them
+    // leaves the block unrun. Resolver methods are called with method syntax so that the
+    // resolver expression can be a container, a reference to one or a smart pointer to one.
+    let pats = args.iter()
+        .filter_map(|arg| match arg {
+            FnArg::Captured(ArgCaptured{ pat, ty, .. }) => Some((pat.clone(), ty.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let resolved = resolve_args(args)?;
+    let bindings = resolved.iter().map(|arg| &arg.binding);
+    let methods = resolved.iter().map(|arg| &arg.resolve_method);
+    let passes = resolved.iter().map(|arg| &arg.pass);
+    let pats_and_types = pats.iter().map(|(pat, ty)| quote!{ #pat: #ty });
+
+    // The resolver expression is grouped so that it is borrowed as a whole (e.g. for a + b)
+    let resolver = Group::new(Delimiter::None, quote!{ #resolver });
+
+    Ok(quote!{
+        {
+            #[allow(unused_imports)]
+            use #resolver_trait;
+            #[allow(unused_imports)]
+            use #shared_resolver_trait;
+            let __rustdi_resolver = &#resolver;
+            #(let #bindings = __rustdi_resolver.#methods()?;)*
+            #(let #pats_and_types = #passes;)*
+            #block
+        }
+    })
+}
@@ -0,0 +1,9 @@
+#[macro_use] extern crate rustdi_derive;
+
+#[derive(Injectable)]
+enum Service {
+    Local,
+    Remote,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+extern crate rustdi;
+#[macro_use] extern crate rustdi_derive;
+
+#[derive(Injectable)]
+struct Service {
+    #[named(primary)]
+    count: u32,
+}
+
+fn main() {}
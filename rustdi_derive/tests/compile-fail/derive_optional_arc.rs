@@ -0,0 +1,11 @@
+extern crate rustdi;
+#[macro_use] extern crate rustdi_derive;
+
+use std::sync::Arc;
+
+#[derive(Injectable)]
+struct Service {
+    count: Option<Arc<u32>>,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+extern crate rustdi;
+extern crate rustdi_derive;
+
+use rustdi_derive::inject;
+
+#[inject(error = rustdi::ResolveError, resolver = rustdi::ServiceContainer, error = rustdi::ResolveError)]
+fn handler(count: &u32) {
+    println!("{}", count);
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+extern crate rustdi_derive;
+
+use rustdi_derive::inject;
+
+struct Controller;
+
+impl Controller {
+    #[inject]
+    fn handler(#[from_resolver] &self, count: &u32) {
+        println!("{}", count);
+    }
+}
+
+fn main() {}
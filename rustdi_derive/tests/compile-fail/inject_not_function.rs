@@ -0,0 +1,8 @@
+extern crate rustdi_derive;
+
+use rustdi_derive::inject;
+
+#[inject]
+struct Handler;
+
+fn main() {}
@@ -0,0 +1,10 @@
+extern crate rustdi_derive;
+
+use rustdi_derive::inject;
+
+#[inject]
+fn handler(count: Option<std::sync::Arc<u32>>) {
+    println!("{:?}", count);
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+extern crate rustdi_derive;
+
+use rustdi_derive::inject;
+
+#[inject(singleton = true)]
+fn handler(count: &u32) {
+    println!("{}", count);
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+extern crate rustdi_derive;
+
+use rustdi_derive::inject;
+
+#[inject]
+fn handler(count: &u32, bytes: [u8]) {
+    println!("{} {}", count, bytes.len());
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+extern crate rustdi_derive;
+
+use rustdi_derive::injectable;
+
+struct Service;
+
+impl Service {
+    #[injectable]
+    fn new(count: &u32) {
+        println!("{}", count);
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+extern crate rustdi_derive;
+
+use rustdi_derive::injectable;
+
+struct Service;
+
+impl Service {
+    #[injectable]
+    fn new(&self, count: &u32) -> Self {
+        println!("{}", count);
+        Service
+    }
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[macro_use] extern crate rustdi_derive;
+
+fn main() {
+    let _handler = injected!(|count: &u32, state| {
+        println!("{} {}", count, state);
+    });
+}
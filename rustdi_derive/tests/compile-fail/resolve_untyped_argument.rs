@@ -0,0 +1,11 @@
+#[macro_use] extern crate rustdi_derive;
+extern crate rustdi;
+
+fn read(container: &rustdi::ServiceContainer) -> Result<(), rustdi::ResolveError> {
+    resolve!(container => state; {
+        let _ = state;
+    });
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+extern crate trybuild;
+
+// Unsupported uses of the macros should produce errors pointing at the offending code
+#[test]
+fn compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/compile-fail/*.rs");
+}
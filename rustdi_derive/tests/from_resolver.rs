@@ -0,0 +1,69 @@
+extern crate rustdi;
+extern crate rustdi_derive;
+
+use std::sync::Arc;
+use rustdi::{FromResolver, Handler, Inject, ResolveError, Resolver, ServiceContainer};
+use rustdi_derive::inject;
+
+struct Config {
+    name: &'static str,
+    port: u16,
+}
+
+// Part of the config, which handlers can take rather than depending on the whole config
+struct Port(u16);
+
+impl<R: Resolver> FromResolver<R> for Port {
+    fn from_resolver(resolver: &R) -> Result<Self, R::Error> {
+        Ok(Port(resolver.resolve_immutable_ref::<Config>()?.port))
+    }
+}
+
+// Only produced by containers, e.g. as it needs something only they provide
+struct Services(usize);
+
+impl FromResolver<ServiceContainer> for Services {
+    fn from_resolver(container: &ServiceContainer) -> Result<Self, ResolveError> {
+        let configs = container.resolve_optional_immutable_ref::<Config>()?.into_iter().count();
+        Ok(Services(configs))
+    }
+}
+
+#[inject]
+fn address(config: &Config, #[from_resolver] port: Port) -> String {
+    format!("{}:{}", config.name, port.0)
+}
+
+#[inject]
+fn count(#[from_resolver] services: Services) -> usize {
+    services.0
+}
+
+fn container() -> ServiceContainer {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config{name: "localhost", port: 8080}));
+    c
+}
+
+#[test]
+fn from_resolver_arguments_are_produced_by_their_type() {
+    let c = container();
+    assert_eq!(address(&c).unwrap(), "localhost:8080");
+    assert_eq!(address::Handler.inject(&c).unwrap(), "localhost:8080");
+    assert_eq!(count(&c).unwrap(), 1);
+    assert_eq!(count::Handler.inject(&ServiceContainer::new()).unwrap(), 0);
+}
+
+#[test]
+fn from_resolver_errors_are_returned() {
+    let c = ServiceContainer::new();
+    assert!(matches!(address(&c), Err(ResolveError::NonExist)));
+}
+
+#[test]
+fn from_resolver_arguments_are_not_dependencies() {
+    let dependencies = address::Handler.dependencies();
+    assert_eq!(dependencies.len(), 1);
+    assert!(dependencies[0].type_id == ::std::any::TypeId::of::<Config>());
+    assert!(count::Handler.dependencies().is_empty());
+}
@@ -0,0 +1,50 @@
+extern crate rustdi;
+extern crate rustdi_derive;
+
+use std::sync::Arc;
+use rustdi::{Access, Inject, ServiceContainer};
+use rustdi_derive::inject;
+
+// A service whose name is the same as the Handler type generated for each function
+pub struct Handler(&'static str);
+
+#[inject]
+pub fn handle(handler: &Handler) -> &'static str {
+    handler.0
+}
+
+#[test]
+fn dependency_named_handler_is_listed() {
+    use rustdi::Handler as HandlerTrait;
+
+    let dependencies = handle::Handler.dependencies();
+    assert_eq!(dependencies.len(), 1);
+    assert!(dependencies[0].type_id == ::std::any::TypeId::of::<Handler>());
+    assert!(matches!(dependencies[0].access, Access::ImmutableRef));
+}
+
+#[test]
+fn handler_named_dependency_is_checked_and_injected() {
+    let mut c = ServiceContainer::new();
+    assert!(c.check_handler(&handle::Handler).is_err());
+
+    c.bind_singleton_arc(Arc::new(Handler("injected")));
+    assert!(c.check_handler(&handle::Handler).is_ok());
+    assert_eq!(handle::Handler.inject(&c).unwrap(), "injected");
+}
+
+#[test]
+fn inject_works_inside_a_function_body() {
+    struct Greeting(&'static str);
+
+    #[inject]
+    fn greet(greeting: &Greeting, #[arg] subject: &str) -> String {
+        format!("{} {}", greeting.0, subject)
+    }
+
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Greeting("hello")));
+    assert!(c.check_handler(&greet::Handler).is_ok());
+    assert_eq!(greet(&c, "world").unwrap(), "hello world");
+    assert_eq!(greet::call(&Greeting("hi"), "there"), "hi there");
+}
@@ -0,0 +1,113 @@
+extern crate rustdi;
+#[macro_use] extern crate rustdi_derive;
+
+use std::sync::{Arc, Mutex, RwLock};
+use rustdi::{Resolver, ResolveError, ServiceContainer};
+
+#[derive(Clone)]
+struct Config(&'static str);
+struct State(u32);
+struct Log(Vec<&'static str>);
+#[derive(Clone)]
+struct Client(&'static str);
+#[derive(Clone)]
+struct Metrics;
+
+#[derive(Injectable)]
+struct Uploader {
+    config: Arc<Config>,
+    state: Arc<RwLock<State>>,
+    log: Arc<Mutex<Log>>,
+    client: Client,
+    #[named("archive")]
+    archive: Client,
+    metrics: Option<Metrics>,
+}
+
+#[derive(Injectable)]
+struct Pair(Client, Option<Metrics>);
+
+fn container() -> ServiceContainer {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config("frogs")));
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(State(1))));
+    c.bind_singleton_mutex(Arc::new(Mutex::new(Log(vec![]))));
+    c.bind_factory(|_| Client("primary"));
+    c.bind_named("archive", |c| c.bind_factory(|_| Client("archive")));
+    c
+}
+
+#[test]
+fn derived_fields_are_resolved_by_type() {
+    let mut c = container();
+    c.bind_injectable::<Uploader>();
+
+    let uploader = c.resolve_owned_value::<Uploader>().unwrap();
+    assert_eq!(uploader.config.0, "frogs");
+    assert_eq!(uploader.state.read().unwrap().0, 1);
+    uploader.log.lock().unwrap().0.push("uploaded");
+    assert_eq!(uploader.client.0, "primary");
+    assert_eq!(uploader.archive.0, "archive");
+    assert!(uploader.metrics.is_none());
+
+    // The shared fields are handles to the container's singletons
+    uploader.state.write().unwrap().0 += 1;
+    assert_eq!(c.resolve_immutable_ref::<State>().unwrap().0, 2);
+    assert_eq!(c.resolve_immutable_ref::<Log>().unwrap().0, vec!["uploaded"]);
+}
+
+#[test]
+fn derived_optional_fields_are_resolved_if_bound() {
+    let mut c = container();
+    c.bind_factory(|_| Metrics);
+    c.bind_injectable::<Pair>();
+
+    let pair = c.resolve_owned_value::<Pair>().unwrap();
+    assert_eq!((pair.0).0, "primary");
+    assert!(pair.1.is_some());
+}
+
+#[test]
+fn derived_missing_fields_are_errors() {
+    let mut c = ServiceContainer::new();
+    c.bind_injectable::<Uploader>();
+    assert!(matches!(c.resolve_owned_value::<Uploader>().map(|_| ()), Err(ResolveError::NonExist)));
+}
+
+// A type with both a derived Injectable implementation and an injectable constructor
+#[derive(Injectable)]
+struct Reporter {
+    subject: String,
+}
+
+impl Reporter {
+    #[injectable]
+    fn new(config: &Config, client: Client) -> Self {
+        Reporter{subject: format!("{} via {}", config.0, client.0)}
+    }
+
+    #[injectable]
+    fn with_state(state: &mut State) -> Self {
+        state.0 += 1;
+        Reporter{subject: format!("state {}", state.0)}
+    }
+}
+
+#[test]
+fn injectable_constructors_are_bound_as_factories() {
+    let mut c = container();
+    c.bind_try_factory(Reporter::new_injected);
+    assert_eq!(c.resolve_owned_value::<Reporter>().unwrap().subject, "frogs via primary");
+
+    c.bind_try_factory(Reporter::with_state_injected);
+    assert_eq!(c.resolve_owned_value::<Reporter>().unwrap().subject, "state 2");
+    assert_eq!(c.resolve_owned_value::<Reporter>().unwrap().subject, "state 3");
+}
+
+#[test]
+fn injectable_constructors_dont_hide_derived_injectable() {
+    let mut c = container();
+    c.bind_factory(|_| String::from("derived"));
+    c.bind_injectable::<Reporter>();
+    assert_eq!(c.resolve_owned_value::<Reporter>().unwrap().subject, "derived");
+}
@@ -0,0 +1,53 @@
+extern crate rustdi;
+#[macro_use] extern crate rustdi_derive;
+
+use std::sync::{Arc, RwLock};
+use rustdi::{Inject, ResolveError, Resolver, ServiceContainer};
+
+struct Config(&'static str);
+struct Count(u32);
+
+fn container() -> ServiceContainer {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config("hello")));
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(Count(0))));
+    c
+}
+
+// Runs an injected callback, as e.g. a router or a job queue would
+fn run<H: Inject<Ret, R>, Ret, R: Resolver>(handler: &H, resolver: &R) -> Result<H::Return, R::Error> {
+    handler.inject(resolver)
+}
+
+#[test]
+fn injected_closures_resolve_their_arguments() {
+    let c = container();
+    let suffix = String::from("!");
+    let handler = injected!(|config: &Config, count: &mut Count, missing: Option<&String>| {
+        count.0 += 1;
+        format!("{} {}{}{}", config.0, count.0, suffix, missing.map_or("", |s| s.as_str()))
+    });
+
+    assert_eq!(run(&handler, &c).unwrap(), "hello 1!");
+    assert_eq!(handler.inject(&c).unwrap(), "hello 2!");
+}
+
+#[test]
+fn injected_closures_can_be_boxed() {
+    let c = container();
+    let handlers: Vec<Box<dyn Inject<(), ServiceContainer, Return = ()>>> = vec![
+        Box::new(injected!(|count: &mut Count| count.0 += 1)),
+        Box::new(injected!(|count: &mut Count| count.0 *= 10)),
+        Box::new(injected!(|| ())),
+    ];
+    for handler in &handlers {
+        handler.inject(&c).unwrap();
+    }
+    assert_eq!(c.resolve_immutable_ref::<Count>().unwrap().0, 10);
+}
+
+#[test]
+fn injected_closures_return_resolve_errors() {
+    let handler = injected!(|config: &Config| config.0);
+    assert!(matches!(handler.inject(&ServiceContainer::new()), Err(ResolveError::NonExist)));
+}
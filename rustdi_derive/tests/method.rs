@@ -0,0 +1,65 @@
+extern crate rustdi;
+extern crate rustdi_derive;
+
+use std::sync::{Arc, RwLock};
+use rustdi::ServiceContainer;
+use rustdi_derive::inject;
+
+struct Greeting(&'static str);
+struct Count(u32);
+
+struct Controller {
+    punctuation: &'static str,
+    calls: u32,
+}
+
+impl Controller {
+    #[inject]
+    fn greet(&self, greeting: &Greeting) -> String {
+        format!("{} world{}", greeting.0, self.punctuation)
+    }
+
+    #[inject]
+    fn count(&mut self, count: &mut Count) -> u32 {
+        self.calls += 1;
+        count.0 += self.calls;
+        count.0
+    }
+
+    #[inject]
+    fn finish(mut self, greeting: &Greeting) -> String {
+        self.punctuation = ".";
+        format!("{} after {} calls{}", greeting.0, self.calls, self.punctuation)
+    }
+}
+
+fn container() -> ServiceContainer {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Greeting("hello")));
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(Count(0))));
+    c
+}
+
+#[test]
+fn methods_pass_self_through() {
+    let c = container();
+    let controller = Controller{punctuation: "!", calls: 0};
+    assert_eq!(controller.greet(&c).unwrap(), "hello world!");
+    assert_eq!(controller.greet_orig(&Greeting("hi")), "hi world!");
+}
+
+#[test]
+fn methods_take_mutable_and_owned_self() {
+    let c = container();
+    let mut controller = Controller{punctuation: "!", calls: 0};
+    assert_eq!(controller.count(&c).unwrap(), 1);
+    assert_eq!(controller.count(&c).unwrap(), 3);
+    assert_eq!(controller.finish(&c).unwrap(), "hello after 2 calls.");
+}
+
+#[test]
+fn methods_return_resolve_errors() {
+    let c = ServiceContainer::new();
+    let controller = Controller{punctuation: "!", calls: 0};
+    assert!(controller.greet(&c).is_err());
+}
@@ -0,0 +1,155 @@
+extern crate rustdi;
+extern crate rustdi_derive;
+
+use std::any::TypeId;
+use std::sync::Arc;
+use rustdi::{Inject, ResolveError, Resolver, ServiceContainer, ServiceReadGuard, ServiceWriteGuard};
+use rustdi_derive::inject;
+
+struct Config(&'static str);
+
+// The user making a request, which only RequestResolver can resolve
+struct User(&'static str);
+
+struct RequestResolver {
+    user: User,
+    container: ServiceContainer,
+}
+
+impl Resolver for RequestResolver {
+    type Error = ResolveError;
+
+    fn resolve_immutable_ref<S: 'static>(&self) -> Result<ServiceReadGuard<'_, S>, ResolveError> {
+        // Use of transmute is safe as the types must be identical if their TypeIds are
+        if TypeId::of::<S>() == TypeId::of::<User>() {
+            return Ok(ServiceReadGuard::Ref(unsafe { ::std::mem::transmute::<&User, &S>(&self.user) }));
+        }
+        self.container.resolve_immutable_ref::<S>()
+    }
+
+    fn resolve_mutable_ref<S: 'static>(&self) -> Result<ServiceWriteGuard<'_, S>, ResolveError> {
+        self.container.resolve_mutable_ref::<S>()
+    }
+
+    fn resolve_owned_value<S: 'static>(&self) -> Result<S, ResolveError> {
+        self.container.resolve_owned_value::<S>()
+    }
+
+    fn is_not_bound(error: &ResolveError) -> bool {
+        matches!(error, ResolveError::NonExist)
+    }
+}
+
+#[derive(Debug)]
+enum AppError {
+    Resolve(ResolveError),
+    Invalid(&'static str),
+}
+
+type AppResult<T> = Result<T, AppError>;
+
+// An error type which handler errors and resolution errors can both be converted into
+#[derive(Debug)]
+struct ReportError(String);
+
+impl From<AppError> for ReportError {
+    fn from(error: AppError) -> Self {
+        ReportError(format!("{:?}", error))
+    }
+}
+
+impl From<ResolveError> for ReportError {
+    fn from(error: ResolveError) -> Self {
+        ReportError(error.to_string())
+    }
+}
+
+impl From<ResolveError> for AppError {
+    fn from(error: ResolveError) -> Self {
+        AppError::Resolve(error)
+    }
+}
+
+#[inject(resolver = RequestResolver)]
+fn greet_user(user: &User, config: &Config) -> String {
+    format!("{} {}", config.0, user.0)
+}
+
+#[inject(error = AppError)]
+fn describe(config: &Config) -> String {
+    config.0.to_string()
+}
+
+#[inject(resolver = RequestResolver, error = AppError)]
+fn user_name(user: &User) -> &'static str {
+    user.0
+}
+
+#[inject(flatten)]
+fn parse_port(config: &Config) -> Result<u16, AppError> {
+    config.0.parse().map_err(|_| AppError::Invalid(config.0))
+}
+
+#[inject(flatten)]
+fn aliased_port(config: &Config) -> AppResult<u16> {
+    config.0.parse().map_err(|_| AppError::Invalid(config.0))
+}
+
+#[inject(flatten, error = ReportError)]
+fn report_port(config: &Config) -> AppResult<u16> {
+    config.0.parse().map_err(|_| AppError::Invalid(config.0))
+}
+
+fn config(port: &'static str) -> ServiceContainer {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config(port)));
+    c
+}
+
+fn resolver(container: ServiceContainer) -> RequestResolver {
+    RequestResolver{user: User("frogs"), container}
+}
+
+#[test]
+fn concrete_resolver_is_used() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config("hello")));
+    let resolver = resolver(c);
+
+    assert_eq!(greet_user(&resolver).unwrap(), "hello frogs");
+    assert_eq!(greet_user::Handler.inject(&resolver).unwrap(), "hello frogs");
+}
+
+#[test]
+fn resolve_errors_are_converted() {
+    let c = ServiceContainer::new();
+    assert!(matches!(describe(&c), Err(AppError::Resolve(ResolveError::NonExist))));
+
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config("hello")));
+    assert_eq!(describe(&c).unwrap(), "hello");
+}
+
+#[test]
+fn concrete_resolver_errors_are_converted() {
+    let resolver = resolver(ServiceContainer::new());
+    assert_eq!(user_name(&resolver).unwrap(), "frogs");
+    assert!(matches!(greet_user(&resolver), Err(ResolveError::NonExist)));
+}
+
+#[test]
+fn flattened_results_are_unwrapped() {
+    assert_eq!(parse_port(&config("80")).unwrap(), 80);
+    assert_eq!(aliased_port(&config("443")).unwrap(), 443);
+    assert_eq!(report_port(&config("8080")).unwrap(), 8080);
+}
+
+#[test]
+fn flattened_errors_are_combined() {
+    assert!(matches!(parse_port(&config("eighty")), Err(AppError::Invalid("eighty"))));
+    assert!(matches!(parse_port(&ServiceContainer::new()), Err(AppError::Resolve(ResolveError::NonExist))));
+    assert!(matches!(aliased_port(&ServiceContainer::new()), Err(AppError::Resolve(ResolveError::NonExist))));
+
+    assert_eq!(report_port(&config("eighty")).unwrap_err().0, "Invalid(\"eighty\")");
+    assert_eq!(report_port(&ServiceContainer::new()).unwrap_err().0, ResolveError::NonExist.to_string());
+}
@@ -0,0 +1,63 @@
+extern crate rustdi;
+#[macro_use] extern crate rustdi_derive;
+
+use std::sync::{Arc, RwLock};
+use rustdi::{ResolveError, ServiceContainer};
+
+struct Config(&'static str);
+struct Count(u32);
+#[derive(Clone)]
+struct Client(&'static str);
+
+fn container() -> ServiceContainer {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config("hello")));
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(Count(0))));
+    c.bind_factory(|_| Client("s3"));
+    c
+}
+
+fn count_up(c: &ServiceContainer) -> Result<String, ResolveError> {
+    resolve!(c => config: &Config, count: &mut Count, client: Client, names: Option<&String>; {
+        count.0 += 1;
+        Ok(format!("{} {} {} {}", config.0, count.0, client.0, names.is_none()))
+    })
+}
+
+#[test]
+fn resolve_binds_services_for_the_block() -> Result<(), ResolveError> {
+    let c = container();
+    assert_eq!(count_up(&c)?, "hello 1 s3 true");
+    assert_eq!(count_up(&c)?, "hello 2 s3 true");
+
+    // The guards are released at the end of the block, so the services can be resolved again
+    resolve!(c => count: &mut Count; { count.0 = 10; });
+    assert_eq!(count_up(&c)?, "hello 11 s3 true");
+    Ok(())
+}
+
+#[test]
+fn resolve_accepts_any_resolver_expression() {
+    let shared = Arc::new(container());
+    let config = (|| -> Result<&'static str, ResolveError> {
+        resolve!(shared => config: &Config, count: &mut Count; {
+            count.0 = 5;
+            Ok(config.0)
+        })
+    })();
+    assert_eq!(config.unwrap(), "hello");
+    assert_eq!(count_up(&shared).unwrap(), "hello 6 s3 true");
+}
+
+#[test]
+fn resolve_returns_errors_without_running_the_block() {
+    let mut ran = false;
+    let result = (|| -> Result<(), ResolveError> {
+        resolve!(ServiceContainer::new() => _config: &Config; {
+            ran = true;
+        });
+        Ok(())
+    })();
+    assert!(matches!(result, Err(ResolveError::NonExist)));
+    assert!(!ran);
+}
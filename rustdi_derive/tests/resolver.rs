@@ -0,0 +1,82 @@
+extern crate rustdi;
+extern crate rustdi_derive;
+
+use std::sync::Arc;
+use rustdi::{ResolveError, Resolver, ServiceContainer, ServiceReadGuard, ServiceWriteGuard};
+use rustdi_derive::inject;
+
+// A resolver which only implements the methods Resolver requires
+struct MinimalResolver(ServiceContainer);
+
+impl Resolver for MinimalResolver {
+    type Error = ResolveError;
+
+    fn resolve_immutable_ref<S: 'static>(&self) -> Result<ServiceReadGuard<'_, S>, ResolveError> {
+        self.0.resolve_immutable_ref::<S>()
+    }
+
+    fn resolve_mutable_ref<S: 'static>(&self) -> Result<ServiceWriteGuard<'_, S>, ResolveError> {
+        self.0.resolve_mutable_ref::<S>()
+    }
+
+    fn resolve_owned_value<S: 'static>(&self) -> Result<S, ResolveError> {
+        self.0.resolve_owned_value::<S>()
+    }
+
+    fn is_not_bound(error: &ResolveError) -> bool {
+        matches!(error, ResolveError::NonExist)
+    }
+}
+
+struct Config(&'static str);
+struct Counter(u32);
+
+#[inject]
+fn borrowing_handler(config: &Config, counter: Option<&mut Counter>) -> String {
+    match counter {
+        Some(counter) => { counter.0 += 1; format!("{} {}", config.0, counter.0) },
+        None => config.0.to_string(),
+    }
+}
+
+#[inject]
+fn shared_handler(config: Arc<Config>) -> &'static str {
+    config.0
+}
+
+#[test]
+fn minimal_resolver_injects_borrows_and_optional_services() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config("count")));
+    c.bind_singleton_rwlock(Arc::new(::std::sync::RwLock::new(Counter(0))));
+    let resolver = MinimalResolver(c);
+
+    assert_eq!(borrowing_handler(&resolver).unwrap(), "count 1");
+    assert_eq!(borrowing_handler(&resolver).unwrap(), "count 2");
+}
+
+#[test]
+fn minimal_resolver_injects_none_for_unbound_optional_services() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config("count")));
+    let resolver = MinimalResolver(c);
+
+    assert_eq!(borrowing_handler(&resolver).unwrap(), "count");
+}
+
+#[test]
+fn minimal_resolver_returns_other_errors_for_optional_services() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config("count")));
+    c.bind_singleton_arc(Arc::new(Counter(0)));
+    let resolver = MinimalResolver(c);
+
+    assert!(matches!(borrowing_handler(&resolver), Err(ResolveError::MutImmutable)));
+}
+
+#[test]
+fn shared_resolver_injects_arcs() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config("shared")));
+    assert_eq!(shared_handler(&c).unwrap(), "shared");
+}
@@ -0,0 +1,60 @@
+extern crate rustdi;
+extern crate rustdi_derive;
+
+use std::sync::{Arc, RwLock};
+use rustdi::ServiceContainer;
+use rustdi_derive::inject;
+
+trait Greeter {
+    fn greet(&self, subject: &str) -> String;
+}
+
+struct English;
+
+impl Greeter for English {
+    fn greet(&self, subject: &str) -> String {
+        format!("hello {}", subject)
+    }
+}
+
+#[inject]
+fn describe(name: &str, ports: &[u16]) -> String {
+    format!("{} on {:?}", name, ports)
+}
+
+#[inject]
+fn add_port(ports: &mut [u16]) {
+    ports[0] += 1;
+}
+
+#[inject]
+fn greet(greeter: &dyn Greeter, subject: Option<&str>) -> String {
+    greeter.greet(subject.unwrap_or("world"))
+}
+
+#[inject]
+fn greet_bounded(greeter: &(dyn Greeter + Send)) -> String {
+    greeter.greet("bounds")
+}
+
+#[test]
+fn str_and_slices_are_resolved_from_string_and_vec() {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(String::from("frogs")));
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(vec![80u16, 443])));
+
+    assert_eq!(describe(&c).unwrap(), "frogs on [80, 443]");
+    add_port(&c).unwrap();
+    assert_eq!(describe(&c).unwrap(), "frogs on [81, 443]");
+}
+
+#[test]
+fn trait_objects_are_resolved_from_send_sync_boxes() {
+    let mut c = ServiceContainer::new();
+    c.bind_factory(|_| Box::new(English) as Box<dyn Greeter + Send + Sync>);
+    assert_eq!(greet(&c).unwrap(), "hello world");
+    assert_eq!(greet_bounded(&c).unwrap(), "hello bounds");
+
+    c.bind_singleton_arc(Arc::new(String::from("toads")));
+    assert_eq!(greet(&c).unwrap(), "hello toads");
+}
@@ -3,16 +3,69 @@ extern crate rustdi;
 
 use std::sync::Arc;
 use std::sync::RwLock;
+use std::future::Future;
+use std::task::{Context, Poll, Waker};
 
-use rustdi::{Resolver, ServiceContainer};
+use rustdi::{GenericFactory, Inject, Resolver, ResolveError, ServiceContainer, ServiceReadGuard, ServiceWriteGuard};
 
 pub mod common{
     pub mod models;
     pub mod handlers;
 }
-use common::models::{AppConfig, AppState, s3};
-use common::handlers::{read_handler, write_handler, s3_handler};
+use common::models::{AppConfig, AppState, Uploader, Reporter, s3, db};
+use common::handlers::{read_handler, write_handler, s3_handler, metrics_handler, db_handler, snapshot_handler, show, greeting_handler, user_handler, subject_echo_handler, background_handler, deferred_handler, report_handler, subject_handler, GreetingController};
 
+// A single generic factory which creates a repository for any model type
+struct RepositoryFactory;
+impl<T: Send + Sync + 'static> GenericFactory<T> for RepositoryFactory {
+    type Service = db::Repository<T>;
+
+    fn create(_: &ServiceContainer) -> db::Repository<T> {
+        db::Repository::default()
+    }
+}
+
+// A minimal executor for running async handlers to completion
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        std::thread::yield_now();
+    }
+}
+
+// Resolve services for a block at a time with resolve!, which returns from this function
+// if any of them can't be resolved
+fn resolve_manually(container: &ServiceContainer) -> Result<(), ResolveError> {
+    resolve!(container => state: &mut AppState; {
+        state.subject = "frogs".into();
+    });
+    resolve!(container => state: &AppState, client: s3::S3Client; {
+        println!("Hello {}", state.subject);
+        client.list_objects();
+    });
+
+    // Guards can be narrowed to just part of a service, keeping the service's lock held
+    {
+        let mut subject = ServiceWriteGuard::map(container.resolve_mutable_ref::<AppState>()?, |state| &mut state.subject);
+        subject.push_str(" and toads");
+    }
+    let subject = ServiceReadGuard::map(container.resolve_immutable_ref::<AppState>()?, |state| state.subject.as_str());
+    println!("Mapped subject: {}", &*subject);
+    let bucket = container.resolve_with::<s3::Bucket, _>("images")?;
+    println!("Using bucket {}", bucket.name);
+    resolve!(container => users: db::Repository<db::User>, orders: db::Repository<db::Order>; {
+        println!("Found {} users and {} orders", users.find_all().len(), orders.find_all().len());
+    });
+    resolve!(container => uploader: Uploader, reporter: Reporter; {
+        uploader.upload();
+        println!("Reporting on {}", reporter.subject);
+    });
+    Ok(())
+}
 
 fn main() {
 
@@ -20,32 +73,83 @@ fn main() {
     let container = {
         let mut c = ServiceContainer::new();
         c.bind_singleton_arc(Arc::new(AppConfig));
-        c.bind_singleton_rwlock(Arc::new(RwLock::new(AppState{
+        c.bind_singleton_clone(Arc::new(RwLock::new(AppState{
             greeting: "hello".into(),
             subject:  "world".into(),
         })));
         c.bind_factory(|_| s3::S3Client());
+        c.bind_factory_with_args(|c, name: &'static str| Ok(s3::Bucket{
+            client: c.resolve_owned_value()?,
+            name: name.into(),
+        }));
+        c.bind_pool(4, |_| db::Connection());
+        c.bind_generic::<RepositoryFactory, (db::User, db::Order)>();
+        c.bind_named("archive", |c| c.bind_factory(|_| s3::S3Client()));
+        c.bind_injectable::<Uploader>();
+        c.bind_try_factory(Reporter::new_injected);
         Arc::new(c)
     };
 
     // Test resolving references out of the container manually
     println!("Testing container manually...");
-    {
-        let mut state = container.resolve_mutable_ref::<AppState>().unwrap();
-        state.subject = "frogs".into();
-    }
-    {
-        let state = container.resolve_immutable_ref::<AppState>().unwrap();
-        println!("Hello {}", state.subject);
-    }
-    let client = container.resolve_owned_value::<s3::S3Client>().unwrap();
-    client.list_objects();
+    resolve_manually(&container).unwrap();
+
+    // Check that the handlers' dependencies are bound before calling any of them
+    println!("Checking handler dependencies...");
+    container.check_all(&[
+        &write_handler::Handler, &read_handler::Handler, &s3_handler::Handler, &metrics_handler::Handler,
+        &db_handler::Handler, &snapshot_handler::Handler, &user_handler::Handler, &subject_echo_handler::Handler,
+        &background_handler::Handler, &deferred_handler::Handler, &show::Handler,
+    ]).expect("Handler dependencies should be bound");
 
     // Test resolving references out of the container using the #[inject] macro
     println!("Testing injectable handlers...");
     write_handler(&*container).unwrap();
     read_handler(&*container).unwrap();
     s3_handler(&*container).unwrap();
+    metrics_handler(&*container).unwrap();
+    db_handler(&*container).unwrap();
+    snapshot_handler(&*container).unwrap();
+    GreetingController{punctuation: "!!!"}.greet(&*container).unwrap();
+    greeting_handler::<AppState, _>(&*container).unwrap();
+    user_handler(&*container, 42, true).unwrap();
+    subject_echo_handler(&*container).unwrap();
+    background_handler(&*container).unwrap().join().unwrap();
+    if let Err(err) = report_handler(&*container) {
+        println!("{}", err);
+    }
+    println!("subject length: {}", subject_handler(&*container).unwrap());
+    println!("{}", block_on(show(&*container)).unwrap());
+    println!("{}", block_on(deferred_handler(&*container).unwrap()));
+
+    // Closures can be injected too
+    let greet = injected!(|_config: &AppConfig, state: &AppState| -> String {
+        format!("{} {} from a closure!", state.greeting, state.subject)
+    });
+    println!("{}", greet.inject(&*container).unwrap());
+
+    // The original handlers can still be called with plain arguments (e.g. in unit tests)
+    println!("Testing handlers without a container...");
+    let state = AppState{greeting: "goodbye".into(), subject: "owls".into()};
+    snapshot_handler::call(state.clone());
+    greeting_handler::call(&state);
+    GreetingController{punctuation: "."}.greet_orig(&state);
+
+    // Weak handles don't keep services alive, and stop upgrading once their binding is gone
+    println!("Testing weak handles...");
+    let weak_state = container.resolve_weak::<AppState>().unwrap();
+    {
+        let state = weak_state.upgrade().unwrap();
+        println!("weak: {} {}!", state.greeting, state.subject);
+    }
+    let weak_config = {
+        let mut c = ServiceContainer::new();
+        c.bind_singleton_arc(Arc::new(AppConfig));
+        c.resolve_weak::<AppConfig>().unwrap()
+    };
+    if let Err(err) = weak_config.upgrade() {
+        println!("{}", err);
+    }
 
     // Test resolving references out of the container using the #[inject] macro
     // with the handlers running in seperate threads
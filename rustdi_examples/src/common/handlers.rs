@@ -1,5 +1,11 @@
 
-use super::models::{AppConfig, AppState, s3};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::future::Future;
+use std::pin::Pin;
+use rustdi::OwnedReadGuard;
+
+use super::models::{AppConfig, AppError, AppState, Greeting, Metrics, Subject, s3, db};
 
 // Use the #[inject] macro to define IoC container compatible handlers
 #[inject]
@@ -18,7 +24,98 @@ pub fn s3_handler(_config: &AppConfig, client: s3::S3Client) {
     client.get_object();
 }
 
-// #[inject]
-// pub fn show(_req: Request, _db: Connection, _s3: self::s3::S3Client) -> impl Future<Item=Response, Error=()> {
-//     return futures::future::ok(Response {});
-// }
\ No newline at end of file
+// Owned values of clonable singletons are a snapshot of the singleton's current value
+#[inject]
+pub fn snapshot_handler(state: AppState) {
+    println!("snapshot: {} {}!", state.greeting, state.subject);
+}
+
+// Mutable references to pooled services check an instance out of the pool
+// for the duration of the handler
+#[inject]
+pub fn db_handler(state: &AppState, conn: &mut db::Connection) {
+    conn.query(&state.subject);
+}
+
+// Arguments marked #[arg] are passed by the caller after the resolver, and the rest are injected
+#[inject]
+pub fn user_handler(#[arg] user_id: u32, users: db::Repository<db::User>, #[arg] verbose: bool) {
+    let found = users.find_all();
+    if verbose {
+        println!("user {}: searched {} users", user_id, found.len());
+    }
+}
+
+// Arguments marked #[from_resolver] are produced by their type's FromResolver implementation
+#[inject]
+pub fn subject_echo_handler(#[from_resolver] subject: Subject, _config: &AppConfig) {
+    println!("echo: {}", subject.0);
+}
+
+// Resolution errors can be converted into the handler's own error type
+#[inject(error = AppError)]
+pub fn report_handler(state: &AppState, metrics: &Metrics) -> String {
+    metrics.record("report");
+    format!("report on {}", state.subject)
+}
+
+// Handlers which return a Result can have it flattened into the wrapper's Result, with
+// resolution errors converted into the handler's error type
+#[inject(flatten)]
+pub fn subject_handler(state: &AppState) -> Result<usize, AppError> {
+    if state.subject.is_empty() {
+        return Err(AppError::Invalid("no subject"));
+    }
+    Ok(state.subject.len())
+}
+
+// Generic handlers keep their type parameters, which are given when calling them
+#[inject]
+pub fn greeting_handler<G: Greeting>(source: &G) {
+    println!("{}?", source.greeting());
+}
+
+// Shared handles keep services alive after the handler returns, e.g. in a background thread
+#[inject]
+pub fn background_handler(_config: Arc<AppConfig>, state: Arc<RwLock<AppState>>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let state = state.read().unwrap();
+        println!("background: {} {}!", state.greeting, state.subject);
+    })
+}
+
+// Owned guards don't borrow from the resolver, so they can be kept by 'static futures
+#[inject]
+pub fn deferred_handler(state: OwnedReadGuard<AppState>) -> Pin<Box<dyn Future<Output = String>>> {
+    Box::pin(async move {
+        format!("deferred: {} {}!", state.greeting, state.subject)
+    })
+}
+
+// Methods can be injected too: self is passed through and the remaining arguments are injected
+pub struct GreetingController {
+    pub punctuation: &'static str,
+}
+
+impl GreetingController {
+    #[inject]
+    pub fn greet(&self, state: &AppState) {
+        println!("{} {}{}", state.greeting, state.subject, self.punctuation);
+    }
+}
+
+// Optional dependencies resolve to None if they haven't been bound
+#[inject]
+pub fn metrics_handler(state: &AppState, metrics: Option<&Metrics>) {
+    if let Some(metrics) = metrics {
+        metrics.record(&state.subject);
+    }
+}
+
+// Async handlers resolve their dependencies up front (references through owned guards) and
+// then return a future which doesn't borrow from the resolver
+#[inject]
+pub async fn show(_config: &AppConfig, state: &AppState, client: s3::S3Client) -> String {
+    client.get_object();
+    format!("{} {}!", state.greeting, state.subject)
+}
\ No newline at end of file
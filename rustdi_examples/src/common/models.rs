@@ -1,3 +1,6 @@
+use std::sync::Arc;
+use std::fmt;
+use rustdi::{FromResolver, ResolveError, Resolver};
 
 // Dummy types for testing DI with
 #[derive(Clone, Debug)]
@@ -9,13 +12,127 @@ pub struct AppState{
     pub subject: String,
 }
 
+pub trait Greeting {
+    fn greeting (&self) -> String;
+}
+
+impl Greeting for AppState {
+    fn greeting (&self) -> String {
+        format!("{} {}", self.greeting, self.subject)
+    }
+}
+
+// Just the subject of the app's state, which handlers can take with #[from_resolver]
+// rather than depending on the whole state
+pub struct Subject(pub String);
+
+impl<R: Resolver> FromResolver<R> for Subject {
+    fn from_resolver(resolver: &R) -> Result<Self, R::Error> {
+        let state = resolver.resolve_immutable_ref::<AppState>()?;
+        Ok(Subject(state.subject.clone()))
+    }
+}
+
+// A domain error type, which handlers can have resolution errors converted into
+#[derive(Debug)]
+pub enum AppError {
+    Unavailable(ResolveError),
+    Invalid(&'static str),
+}
+
+impl From<ResolveError> for AppError {
+    fn from(err: ResolveError) -> Self {
+        AppError::Unavailable(err)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Unavailable(err) => write!(f, "Service unavailable: {}", err),
+            AppError::Invalid(reason) => write!(f, "Invalid request: {}", reason),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Metrics;
+
+impl Metrics {
+    pub fn record (&self, _event: &str) {}
+}
+
+// Services can construct themselves from their dependencies with #[derive(Injectable)]
+#[derive(Injectable)]
+pub struct Uploader {
+    pub config: Arc<AppConfig>,
+    pub client: s3::S3Client,
+    #[named("archive")]
+    pub archive: s3::S3Client,
+    pub metrics: Option<Metrics>,
+}
+
+impl Uploader {
+    pub fn upload (&self) {
+        self.client.put_object();
+        self.archive.put_object();
+    }
+}
+
+// Existing constructors can be made injectable with #[injectable], which generates
+// a new_injected function that resolves the constructor's arguments
+pub struct Reporter {
+    pub subject: String,
+}
+
+impl Reporter {
+    #[injectable]
+    pub fn new (_config: &AppConfig, state: &AppState) -> Self {
+        Reporter{subject: state.subject.clone()}
+    }
+}
+
 pub mod s3 {
     #[derive(Clone, Debug)]
     pub struct S3Client();
 
+    #[derive(Clone, Debug)]
+    pub struct Bucket {
+        pub client: S3Client,
+        pub name: String,
+    }
+
     impl S3Client {
         pub fn list_objects (&self) {}
         pub fn get_object (&self) {}
         pub fn put_object (&self) {}
     }
-}
\ No newline at end of file
+}
+
+pub mod db {
+    use std::marker::PhantomData;
+
+    #[derive(Debug)]
+    pub struct Connection();
+
+    #[derive(Debug)]
+    pub struct User;
+
+    #[derive(Debug)]
+    pub struct Order;
+
+    #[derive(Debug)]
+    pub struct Repository<T>(PhantomData<T>);
+
+    impl<T> Default for Repository<T> {
+        fn default () -> Self { Repository(PhantomData) }
+    }
+
+    impl<T> Repository<T> {
+        pub fn find_all (&self) -> Vec<T> { Vec::new() }
+    }
+
+    impl Connection {
+        pub fn query (&mut self, _sql: &str) {}
+    }
+}
@@ -7,24 +7,36 @@ extern crate pretty_env_logger;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use std::any::TypeId;
-use hyper::{Body, Chunk, Client, Method, Request, Response, Server, StatusCode, header, Error};
+use hyper::{Body, Method, Request, Response, Server, Error};
+use hyper::header::USER_AGENT;
 use hyper::service::service_fn;
-use futures::{future, Future, Stream};
-use rustdi::{Resolver, ServiceContainer, ResolveError, ServiceReadGuard, ServiceWriteGuard};
+use futures::{future, Future};
+use rustdi::{FromResolver, Inject, Module, Resolver, ServiceContainer, ResolveError, ServiceReadGuard, ServiceWriteGuard};
 
 pub mod common{
     pub mod models;
     pub mod handlers;
 }
 use common::models::{AppConfig, AppState, s3};
-use common::handlers::{read_handler, write_handler};
+use common::handlers::write_handler;
 
-#[inject]
-pub fn route_handler(req: &Request<Body>, state: &mut AppState) {
-    println!("{}", req.uri().path());
+// Handlers which need the request can take the router's resolver type, rather than any resolver
+#[inject(resolver = RequestResolver<Request<Body>, ServiceContainer>)]
+fn route_handler(req: &Request<Body>, #[from_resolver] agent: UserAgent, state: &mut AppState) {
+    println!("{} from {}", req.uri().path(), agent.0);
     state.subject = "penguins".to_string();
 }
 
+// The request's user agent, which handlers can take with #[from_resolver]
+struct UserAgent(String);
+
+impl<R: Resolver> FromResolver<RequestResolver<Request<Body>, R>> for UserAgent {
+    fn from_resolver(resolver: &RequestResolver<Request<Body>, R>) -> Result<Self, R::Error> {
+        let agent = resolver.request.headers().get(USER_AGENT).and_then(|agent| agent.to_str().ok());
+        Ok(UserAgent(agent.unwrap_or("unknown").to_string()))
+    }
+}
+
 struct RequestResolver<ReqT: 'static, ResolverT> {
     pub request: ReqT,
     pub resolver: Arc<ResolverT>,
@@ -33,7 +45,7 @@ struct RequestResolver<ReqT: 'static, ResolverT> {
 impl<ReqT: 'static, ResolverT: Resolver> Resolver for RequestResolver<ReqT, ResolverT> {
     type Error = ResolverT::Error;
 
-    fn resolve_immutable_ref<S: 'static>(&self) -> Result<ServiceReadGuard<S>, Self::Error> {
+    fn resolve_immutable_ref<S: 'static>(&self) -> Result<ServiceReadGuard<'_, S>, Self::Error> {
         
         // Use of mem::transmute is safe as types must be identical if TypeId's are identical
         if TypeId::of::<S>() == TypeId::of::<ReqT>() {
@@ -43,61 +55,112 @@ impl<ReqT: 'static, ResolverT: Resolver> Resolver for RequestResolver<ReqT, Reso
         self.resolver.resolve_immutable_ref::<S>()
     }
 
-    fn resolve_mutable_ref<S: 'static>(&self) -> Result<ServiceWriteGuard<S>, Self::Error> {
+    fn resolve_mutable_ref<S: 'static>(&self) -> Result<ServiceWriteGuard<'_, S>, Self::Error> {
         self.resolver.resolve_mutable_ref::<S>()
     }
 
     fn resolve_owned_value<S: 'static>(&self) -> Result<S, Self::Error> {
         self.resolver.resolve_owned_value::<S>()
     }
+
+    fn is_not_bound(error: &Self::Error) -> bool {
+        ResolverT::is_not_bound(error)
+    }
+
+    fn resolve_optional_immutable_ref<S: 'static>(&self) -> Result<Option<ServiceReadGuard<'_, S>>, Self::Error> {
+        if TypeId::of::<S>() == TypeId::of::<ReqT>() {
+            return self.resolve_immutable_ref::<S>().map(Some);
+        }
+
+        self.resolver.resolve_optional_immutable_ref::<S>()
+    }
+
+    fn resolve_optional_mutable_ref<S: 'static>(&self) -> Result<Option<ServiceWriteGuard<'_, S>>, Self::Error> {
+        self.resolver.resolve_optional_mutable_ref::<S>()
+    }
+
+    fn resolve_optional_owned_value<S: 'static>(&self) -> Result<Option<S>, Self::Error> {
+        self.resolver.resolve_optional_owned_value::<S>()
+    }
 }
 
+type BoxedHandler<R> = Box<dyn Fn(&RequestResolver<Request<Body>, R>) -> Result<(), ResolveError> + Send + Sync + 'static>;
+
 struct Router<R: Resolver> {
     resolver: Arc<R>,
-    routes: HashMap<(Method, String), Box<Fn(&RequestResolver<Request<Body>, R>) -> Result<(), ResolveError> + Send + Sync + 'static>>, 
+    routes: HashMap<(Method, String), BoxedHandler<R>>, 
 }
 
-impl<R: Resolver> Router<R> {
+impl<R: Resolver<Error = ResolveError> + 'static> Router<R> {
     pub fn new (resolver: Arc<R>) -> Router<R> {
         Router{resolver, routes: HashMap::new()}
     }
 
-    pub fn add<H>(&mut self, method: Method, path: &str, handler: H) where H: Fn(&RequestResolver<Request<Body>, R>) -> Result<(), ResolveError> + Send + Sync + 'static {
+    // Handlers can be #[inject] functions or injected! closures
+    pub fn add<H>(&mut self, method: Method, path: &str, handler: H) where H: Inject<(), RequestResolver<Request<Body>, R>, Return = ()> + Send + Sync + 'static {
         let key = (method, path.to_string());
-        let handler = Box::new(handler) as Box<Fn(&RequestResolver<Request<Body>, R>) -> Result<(), ResolveError> + Send + Sync + 'static>;
+        let handler = Box::new(move |resolver: &RequestResolver<Request<Body>, R>| handler.inject(resolver)) as BoxedHandler<R>;
         self.routes.insert(key, handler);
     }
 
-    pub fn lookup(&self, method: Method, path: &str) -> Option<&Box<Fn(&RequestResolver<Request<Body>, R>) -> Result<(), ResolveError> + Send + Sync + 'static>> {
+    pub fn lookup(&self, method: Method, path: &str) -> Option<&BoxedHandler<R>> {
         self.routes.get(&(method, path.to_string()))
     }
 
     pub fn handle_request(&self, request: Request<Body>) {
-        match self.lookup(request.method().clone(), request.uri().path()) {
-            Some(handler) => {
-                let resolver = RequestResolver{request, resolver: self.resolver.clone()};
-                handler(&resolver).unwrap();
-            },
-            None => {},
-        };
+        if let Some(handler) = self.lookup(request.method().clone(), request.uri().path()) {
+            let resolver = RequestResolver{request, resolver: self.resolver.clone()};
+            handler(&resolver).unwrap();
+        }
+    }
+}
+
+// Modules allow each part of the application to bind its own services
+struct ConfigModule;
+impl Module for ConfigModule {
+    fn configure(&self, c: &mut ServiceContainer) {
+        c.bind_singleton_arc(Arc::new(AppConfig));
+    }
+}
+
+struct StateModule;
+impl Module for StateModule {
+    fn configure(&self, c: &mut ServiceContainer) {
+        c.install(ConfigModule);
+        c.bind_singleton_rwlock(Arc::new(RwLock::new(AppState{
+            greeting: "hello".into(),
+            subject:  "world".into(),
+        })));
+    }
+}
+
+struct StorageModule;
+impl Module for StorageModule {
+    fn configure(&self, c: &mut ServiceContainer) {
+        c.install(ConfigModule);
+        c.bind_factory(|_| s3::S3Client());
     }
 }
 
 fn create_container() -> ServiceContainer {
-    let mut c = ServiceContainer::new();
-    c.bind_singleton_arc(Arc::new(AppConfig));
-    c.bind_singleton_rwlock(Arc::new(RwLock::new(AppState{
-        greeting: "hello".into(),
-        subject:  "world".into(),
-    })));
-    c.bind_factory(|_| s3::S3Client());
+    // The active profile (if any) is read from the RUSTDI_PROFILE environment variable
+    let mut c = ServiceContainer::from_env();
+    c.install(StateModule);
+    c.install(StorageModule);
     c
 }
 
 fn create_router(container: Arc<ServiceContainer>) -> Router<ServiceContainer> {
+    // Check handlers' dependencies at startup (route_handler is left out as the
+    // request it depends on is provided by the RequestResolver, not the container)
+    container.check_all(&[&write_handler::Handler]).expect("Handler dependencies should be bound");
+
     let mut r = Router::new(container);
     r.add(Method::GET, "/state/write", write_handler);
     r.add(Method::GET, "/state/echo", route_handler);
+    r.add(Method::GET, "/state/read", injected!(|state: &AppState| {
+        println!("{} {}!", state.greeting, state.subject);
+    }));
     r
 }
 
@@ -0,0 +1,111 @@
+#[macro_use] extern crate rustdi_derive;
+
+use std::sync::{Arc, RwLock};
+use std::future::Future;
+use std::task::{Context, Poll, Waker};
+
+use rustdi::ServiceContainer;
+
+struct Config {
+    greeting: &'static str,
+}
+
+struct State {
+    subject: String,
+}
+
+#[inject]
+async fn greet(config: &Config, state: &mut State) -> String {
+    state.subject.push('!');
+    format!("{} {}", config.greeting, state.subject)
+}
+
+#[inject]
+async fn greet_named(config: &Config, #[arg] name: &str) -> String {
+    format!("{} {}", config.greeting, name)
+}
+
+#[inject]
+async fn greet_shared(config: Arc<Config>, #[arg] name: &str) -> String {
+    format!("{} {}", config.greeting, name)
+}
+
+struct Greeter {
+    punctuation: char,
+}
+
+impl Greeter {
+    #[inject]
+    async fn greet(&self, config: &Config, #[arg] name: &str) -> String {
+        format!("{} {}{}", config.greeting, name, self.punctuation)
+    }
+}
+
+fn container() -> ServiceContainer {
+    let mut c = ServiceContainer::new();
+    c.bind_singleton_arc(Arc::new(Config{greeting: "hello"}));
+    c.bind_singleton_rwlock(Arc::new(RwLock::new(State{subject: "world".into()})));
+    c
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+fn assert_static<F: Future + 'static>(future: F) -> F {
+    future
+}
+
+fn spawn_future<F: Future + Send + 'static>(future: F) -> std::thread::JoinHandle<F::Output> where F::Output: Send {
+    std::thread::spawn(move || block_on(future))
+}
+
+#[test]
+fn async_handler_future_outlives_container() {
+    let container = container();
+    let future = assert_static(greet(&container));
+    drop(container);
+    assert_eq!(block_on(future).unwrap(), "hello world!");
+}
+
+#[test]
+fn async_handler_releases_guards_when_complete() {
+    let container = container();
+    assert_eq!(block_on(greet(&container)).unwrap(), "hello world!");
+    assert_eq!(block_on(greet(&container)).unwrap(), "hello world!!");
+}
+
+#[test]
+fn async_handler_borrows_explicit_arguments() {
+    let container = container();
+    let name = String::from("frogs");
+    let future = greet_named(&container, &name);
+    drop(container);
+    assert_eq!(block_on(future).unwrap(), "hello frogs");
+}
+
+#[test]
+fn async_handler_with_shared_handles_is_send() {
+    let container = container();
+    let name = String::from("frogs");
+    let future = greet_shared(&container, &name);
+    assert_eq!(block_on(future).unwrap(), "hello frogs");
+
+    let future = greet_shared(&container, "toads");
+    drop(container);
+    assert_eq!(spawn_future(future).join().unwrap().unwrap(), "hello toads");
+}
+
+#[test]
+fn async_method_borrows_self_and_explicit_arguments() {
+    let container = container();
+    let greeter = Greeter{punctuation: '?'};
+    let name = String::from("owls");
+    assert_eq!(block_on(greeter.greet(&container, &name)).unwrap(), "hello owls?");
+}